@@ -0,0 +1,99 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::fs;
+use yaml_rust::YamlLoader;
+use yaml_rust::yaml::Yaml;
+use yamlate::environment::{ASTEnvironment, Environment};
+use yamlate::slot_environment::SlotEnvironment;
+use yamlate::vm::{Chunk, Vm};
+use yamlate::yaml::{evaluate, evaluate_with};
+
+// the host inputs that the example documents read
+const INPUTS: [&str; 2] = [
+    "~> current_season := \"spring\"",
+    "~> another_beetle_nearby := 1",
+];
+
+fn new_environment() -> ASTEnvironment {
+    let mut env = ASTEnvironment::new();
+    for input in INPUTS.iter() {
+        evaluate(&Yaml::String((*input).to_owned()), &mut env).unwrap();
+    }
+    env
+}
+
+fn load(path: &str) -> Yaml {
+    let s = fs::read_to_string(path).unwrap();
+    YamlLoader::load_from_str(&s).unwrap().remove(0)
+}
+
+fn bench_document(c: &mut Criterion, name: &str, rules: &[&Yaml]) {
+    let mut group = c.benchmark_group(name);
+
+    group.bench_function("tree_walker", |b| {
+        b.iter(|| {
+            let mut env = new_environment();
+            for rule in rules {
+                evaluate(rule, &mut env).unwrap();
+            }
+        })
+    });
+
+    let mut vm = Vm::new();
+    group.bench_function("vm", |b| {
+        b.iter(|| {
+            let mut env = new_environment();
+            for rule in rules {
+                evaluate_with(rule, &mut env, &mut vm).unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_examples(c: &mut Criterion) {
+    let bug = load("examples/bug.yaml");
+    let example = load("examples/example.yaml");
+    let looped = load("examples/loop.yaml");
+
+    bench_document(
+        c,
+        "bug",
+        &[&bug["cricket"]["wing_color"], &bug["beetle"]["wing_color"]],
+    );
+    bench_document(c, "example", &[&example["foo"]]);
+    bench_document(c, "loop", &[&looped["count"]]);
+}
+
+// runs the body of the loop in examples/loop.yaml with variables looked
+// up by name in each scope and with variables resolved to slots
+fn bench_loop_body(c: &mut Criterion) {
+    let mut group = c.benchmark_group("loop_body");
+    let chunk = Chunk::compile("total = total + i * (2 + 3)").unwrap();
+    let setup = Chunk::compile("i := 7").unwrap();
+
+    let mut vm = Vm::new();
+    let mut env = ASTEnvironment::new();
+    vm.run(&Chunk::compile("total := 0").unwrap(), &mut env)
+        .unwrap();
+    vm.run(&setup, &mut env).unwrap();
+    env.push();
+    env.push();
+    group.bench_function("named", |b| b.iter(|| vm.run(&chunk, &mut env).unwrap()));
+
+    let mut env = SlotEnvironment::new();
+    vm.run(&Chunk::compile("total := 0").unwrap(), &mut env)
+        .unwrap();
+    vm.run(&setup, &mut env).unwrap();
+    env.push();
+    env.push();
+    let symbols = env.bind(&chunk);
+    group.bench_function("slots", |b| {
+        b.iter(|| vm.run_bound(&chunk, &symbols, &mut env).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_examples, bench_loop_body);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+// Generates include/yamlate.h from the functions and types of src/ffi with cbindgen
+// (configured in cbindgen.toml), the header is only rewritten when it changes
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_root_or_default(&crate_dir);
+
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/include/yamlate.h", crate_dir));
+        }
+        // a source that does not parse is reported by the compiler
+        Err(err) => println!("cargo:warning=include/yamlate.h not generated: {}", err),
+    }
+}
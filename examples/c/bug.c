@@ -0,0 +1,74 @@
+/*
+ * Evaluates the wing colors of examples/bug.yaml from C
+ *
+ *   make example && ./target/release/bug examples/bug.yaml
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <yamlate.h>
+
+static char *read_file(const char *path) {
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+        return NULL;
+    }
+    fseek(file, 0, SEEK_END);
+    long length = ftell(file);
+    fseek(file, 0, SEEK_SET);
+
+    char *contents = malloc(length + 1);
+    size_t read = fread(contents, 1, length, file);
+    contents[read] = '\0';
+    fclose(file);
+    return contents;
+}
+
+static void print_error(void) {
+    const char *message = yamlate_last_error_message();
+    fprintf(stderr, "error %d: %s\n", yamlate_last_error_code(), message ? message : "");
+    yamlate_string_free((char *)message);
+}
+
+static int print_wing_color(const Yaml *doc, const char *path, ASTEnvironment *env) {
+    FFIReturnValue_Yaml result = yaml_evaluate_path(doc, path, env);
+    if (result.error != YAMLATE_ERROR_NONE) {
+        print_error();
+        return 1;
+    }
+
+    FFIReturnValue_String color = yaml_string_get(result.value);
+    printf("%s: %s\n", path, color.value);
+    yamlate_string_free((char *)color.value);
+    yaml_destroy((Yaml *)result.value);
+    return 0;
+}
+
+int main(int argc, char **argv) {
+    char *contents = read_file(argc > 1 ? argv[1] : "examples/bug.yaml");
+    if (contents == NULL) {
+        perror("bug.yaml");
+        return 1;
+    }
+
+    FFIReturnValue_Yaml doc = yaml_create_from_string(contents);
+    free(contents);
+    if (doc.error != YAMLATE_ERROR_NONE) {
+        print_error();
+        return 1;
+    }
+
+    ASTEnvironment *env = environment_create();
+    environment_set_integer(env, "another_beetle_nearby", 1);
+    environment_set_string(env, "current_season", "spring");
+
+    int status = print_wing_color(doc.value, "cricket.wing_color", env) ||
+                 print_wing_color(doc.value, "beetle.wing_color", env);
+
+    FFIReturnValue_i32 nearby = environment_get_integer(env, "another_beetle_nearby");
+    printf("another_beetle_nearby: %d\n", nearby.value);
+
+    environment_destroy(env);
+    yaml_destroy((Yaml *)doc.value);
+    return status;
+}
@@ -0,0 +1,70 @@
+#![no_main]
+
+//! Feeds arbitrary documents, paths and variable names through the C API
+//!
+//! Every function must return an error code instead of panicking, so besides
+//! crashes the target fails when a function reports the Panic error.
+
+use libfuzzer_sys::fuzz_target;
+use std::ffi::CString;
+use std::ptr;
+use yamlate::ffi::environment::*;
+use yamlate::ffi::memory::*;
+use yamlate::ffi::node::*;
+use yamlate::ffi::yaml::*;
+
+const PANIC: i32 = -7;
+
+fuzz_target!(|data: &[u8]| {
+    // the input is split on 0xff bytes (which never appear in UTF-8) into a document,
+    // a path and a variable name
+    let mut parts = data.splitn(3, |&b| b == 0xff);
+    let doc = CString::new(parts.next().unwrap_or_default().to_vec()).unwrap_or_default();
+    let path = CString::new(parts.next().unwrap_or_default().to_vec()).unwrap_or_default();
+    let name = CString::new(parts.next().unwrap_or_default().to_vec()).unwrap_or_default();
+
+    unsafe {
+        let env = environment_create_from_string(doc.as_ptr());
+        assert_ne!(env.error, PANIC);
+        let env = if env.value.is_null() {
+            environment_create()
+        } else {
+            env.value
+        };
+        assert_ne!(environment_set_string(env, name.as_ptr(), path.as_ptr()), PANIC);
+        assert_ne!(environment_set_integer(env, path.as_ptr(), 1), PANIC);
+        let value = environment_get_string(env, name.as_ptr());
+        assert_ne!(value.error, PANIC);
+        yamlate_string_free(value.value as *mut _);
+        yamlate_string_free(environment_to_yaml(env) as *mut _);
+
+        let yaml = yaml_create_from_string(doc.as_ptr());
+        assert_ne!(yaml.error, PANIC);
+        let result = yaml_evaluate(yaml.value, env);
+        assert_ne!(result.error, PANIC);
+        yaml_destroy(result.value as *mut _);
+        let result = yaml_evaluate_path(yaml.value, path.as_ptr(), env);
+        assert_ne!(result.error, PANIC);
+        yaml_destroy(result.value as *mut _);
+        let keys = yaml_hash_keys(yaml.value);
+        assert_ne!(keys.error, PANIC);
+        yamlate_string_array_free(keys.value as *mut _, keys.length);
+        let value = yaml_string_get(yaml_get_path(yaml.value, path.as_ptr()).value);
+        assert_ne!(value.error, PANIC);
+        yamlate_string_free(value.value as *mut _);
+        yaml_destroy(yaml.value as *mut _);
+
+        let node = yaml_node_create_from_string(doc.as_ptr());
+        assert_ne!(node.error, PANIC);
+        let child = yaml_node_get_path(node.value, path.as_ptr());
+        assert_ne!(child.error, PANIC);
+        let result = yaml_node_evaluate(child.value, env);
+        assert_ne!(result.error, PANIC);
+        yaml_destroy(result.value as *mut _);
+        yaml_node_destroy(child.value);
+        yaml_node_destroy(node.value);
+
+        environment_destroy(env);
+        environment_destroy(ptr::null_mut());
+    }
+});
@@ -0,0 +1,745 @@
+/*
+ * C API of yamlate, see src/ffi for the documentation of every function.
+ *
+ * Generated from the Rust sources by cbindgen (see build.rs and cbindgen.toml),
+ * do not edit by hand.
+ */
+
+#ifndef YAMLATE_H
+#define YAMLATE_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#if defined(__GNUC__) || defined(__clang__)
+#define YAMLATE_DEPRECATED(note) __attribute__((deprecated(note)))
+#else
+#define YAMLATE_DEPRECATED(note)
+#endif
+
+/* A YAML value (a document, a part of one or a result) */
+typedef struct Yaml Yaml;
+
+/**
+ * Represents an error value returned
+ * from a FFI function
+ */
+typedef enum {
+  YAMLATE_ERROR_NONE = 0,
+  YAMLATE_ERROR_WRONG_TYPE = -1,
+  YAMLATE_ERROR_NOT_DEFINED = -2,
+  YAMLATE_ERROR_INVALID_STRING = -3,
+  YAMLATE_ERROR_EVAL_ERROR = -4,
+  YAMLATE_ERROR_NULL_POINTER = -5,
+  YAMLATE_ERROR_PARSE_ERROR = -6,
+  YAMLATE_ERROR_PANIC = -7,
+  YAMLATE_ERROR_INVALID_ARGUMENT = -8,
+} YamlateError;
+
+/**
+ * Represents a YAML type for FFI
+ */
+typedef enum {
+  YAMLATE_TYPE_INTEGER,
+  YAMLATE_TYPE_REAL,
+  YAMLATE_TYPE_STRING,
+  YAMLATE_TYPE_BOOLEAN,
+  YAMLATE_TYPE_ARRAY,
+  YAMLATE_TYPE_HASH,
+  YAMLATE_TYPE_NULL,
+} YamlateType;
+
+typedef struct ASTEnvironment ASTEnvironment;
+
+/**
+ * A marker for the state of an environment at some point that can be
+ * restored later (like a transaction)
+ */
+typedef struct Snapshot Snapshot;
+
+/**
+ * A handle to a value inside a YAML document
+ *
+ * Every handle into a document shares the document's root through a reference
+ * count, so getting a child handle does not copy anything and the document is
+ * only freed when the last handle into it is destroyed
+ */
+typedef struct YamlNode YamlNode;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  ASTEnvironment *value;
+  int32_t error;
+} FFIReturnValue_Environment;
+
+/**
+ * Represents a change to a variable of an environment for FFI
+ * old_value and expression are null when the variable was
+ * not declared before or the change was not made by an expression
+ */
+typedef struct {
+  const char *name;
+  const Yaml *old_value;
+  const Yaml *new_value;
+  const char *expression;
+} FFIChange;
+
+typedef struct {
+  const FFIChange *value;
+  int32_t length;
+  int32_t error;
+} FFIArrayReturnValue_Change;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  int32_t value;
+  int32_t error;
+} FFIReturnValue_i32;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  const char *value;
+  int32_t error;
+} FFIReturnValue_String;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  double value;
+  int32_t error;
+} FFIReturnValue_f64;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  bool value;
+  int32_t error;
+} FFIReturnValue_bool;
+
+typedef struct {
+  const char *const *value;
+  int32_t length;
+  int32_t error;
+} FFIArrayReturnValue_String;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  const YamlNode *value;
+  int32_t error;
+} FFIReturnValue_YamlNode;
+
+/**
+ * Represents an entry of a hash for FFI as handles to its key and its value
+ */
+typedef struct {
+  const YamlNode *key;
+  const YamlNode *value;
+} FFINodeEntry;
+
+typedef struct {
+  const FFINodeEntry *value;
+  int32_t length;
+  int32_t error;
+} FFIArrayReturnValue_NodeEntry;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  const Yaml *value;
+  int32_t error;
+} FFIReturnValue_Yaml;
+
+typedef struct {
+  const FFIReturnValue_Yaml *value;
+  int32_t length;
+  int32_t error;
+} FFIArrayReturnValue_Yaml;
+
+/**
+ * Represents the return value of a FFI function
+ * includes the value and the error as an integer
+ */
+typedef struct {
+  int64_t value;
+  int32_t error;
+} FFIReturnValue_i64;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns the code of the last error reported on the calling thread,
+ * 0 if no function failed on the thread yet
+ *
+ * Errors are not cleared by functions that succeed so the
+ * last error is only meaningful right after a function failed
+ */
+int32_t yamlate_last_error_code(void);
+
+/**
+ * Returns the description of the last error reported on the calling thread
+ * (like "Variable \"x\" not in environment"), null if no function failed on
+ * the thread yet, the string must be freed with yamlate_string_free
+ */
+const char *yamlate_last_error_message(void);
+
+/**
+ * Returns the path of the document the last error reported on the calling
+ * thread happened at (for the functions evaluating a path), null if there is
+ * no path, the string must be freed with yamlate_string_free
+ */
+const char *yamlate_last_error_path(void);
+
+/**
+ * Forgets the last error reported on the calling thread
+ */
+void yamlate_clear_last_error(void);
+
+ASTEnvironment *environment_create(void);
+
+/**
+ * Creates an environment from YAML or JSON written by environment_to_yaml or
+ * environment_to_json or from a mapping of variable names to values
+ *
+ * # Safety
+ */
+FFIReturnValue_Environment environment_create_from_string(const char *s);
+
+/**
+ * Returns every scope of the environment as YAML,
+ * the string must be freed with yamlate_string_free
+ *
+ * # Safety
+ */
+const char *environment_to_yaml(ASTEnvironment *env);
+
+/**
+ * Returns every scope of the environment as JSON,
+ * the string must be freed with yamlate_string_free
+ *
+ * # Safety
+ */
+const char *environment_to_json(ASTEnvironment *env);
+
+/**
+ * # Safety
+ */
+ASTEnvironment *environment_clone(ASTEnvironment *env);
+
+/**
+ * Starts recording changes to the environment, the returned snapshot
+ * must be passed to either environment_restore or environment_commit
+ *
+ * # Safety
+ */
+Snapshot *environment_snapshot(ASTEnvironment *env);
+
+/**
+ * Undoes every change made since the snapshot was taken and frees the snapshot
+ *
+ * # Safety
+ */
+int32_t environment_restore(ASTEnvironment *env, Snapshot *snapshot);
+
+/**
+ * Keeps every change made since the snapshot was taken and frees the snapshot
+ *
+ * # Safety
+ */
+int32_t environment_commit(ASTEnvironment *env, Snapshot *snapshot);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_lenient(ASTEnvironment *env, bool lenient);
+
+/**
+ * Turns recording the changes made to variables on or off
+ *
+ * # Safety
+ */
+int32_t environment_set_track_changes(ASTEnvironment *env, bool track);
+
+/**
+ * Returns the changes recorded so far in the order they were made,
+ * the list must be freed with environment_changes_destroy
+ *
+ * # Safety
+ */
+FFIArrayReturnValue_Change environment_changes(ASTEnvironment *env);
+
+/**
+ * Frees a list of changes returned by environment_changes
+ *
+ * # Safety
+ */
+void environment_changes_destroy(FFIChange *changes, int32_t length);
+
+/**
+ * Discards the changes recorded so far
+ *
+ * # Safety
+ */
+int32_t environment_clear_changes(ASTEnvironment *env);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_integer(ASTEnvironment *env, const char *name, int32_t value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_string(ASTEnvironment *env, const char *name, const char *value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_decimal(ASTEnvironment *env, const char *name, double value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_boolean(ASTEnvironment *env, const char *name, bool value);
+
+/**
+ * Sets a variable to nil
+ *
+ * # Safety
+ */
+int32_t environment_set_null(ASTEnvironment *env, const char *name);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_const_integer(ASTEnvironment *env, const char *name, int32_t value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_const_string(ASTEnvironment *env, const char *name, const char *value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_const_decimal(ASTEnvironment *env, const char *name, double value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_const_boolean(ASTEnvironment *env, const char *name, bool value);
+
+/**
+ * # Safety
+ */
+int32_t environment_set_const_null(ASTEnvironment *env, const char *name);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_i32 environment_get_integer(ASTEnvironment *env, const char *name);
+
+/**
+ * The returned string (an empty string on error, null if an argument is null)
+ * must be freed with yamlate_string_free
+ *
+ * # Safety
+ */
+FFIReturnValue_String environment_get_string(ASTEnvironment *env, const char *name);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_f64 environment_get_decimal(ASTEnvironment *env, const char *name);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_bool environment_get_boolean(ASTEnvironment *env, const char *name);
+
+/**
+ * Returns the names of the variables visible from the current scope sorted by name,
+ * the array must be freed with yamlate_string_array_free
+ *
+ * # Safety
+ */
+FFIArrayReturnValue_String environment_keys(ASTEnvironment *env);
+
+/**
+ * Returns the type of a variable as a YAML type
+ * (the same values returned by yaml_type)
+ *
+ * # Safety
+ */
+FFIReturnValue_i32 environment_type_of(ASTEnvironment *env, const char *name);
+
+/**
+ * Removes the most current binding of a variable
+ *
+ * # Safety
+ */
+int32_t environment_remove(ASTEnvironment *env, const char *name);
+
+/**
+ * Does nothing if the environment is null
+ *
+ * # Safety
+ */
+void environment_destroy(ASTEnvironment *env);
+
+/**
+ * Frees a string returned by the library (like the value of environment_get_string
+ * or yaml_string_get), does nothing if the string is null
+ *
+ * # Safety
+ */
+void yamlate_string_free(char *s);
+
+/**
+ * Frees an array of strings returned by the library (like the value of
+ * environment_keys or yaml_hash_keys) and every string in it,
+ * does nothing if the array is null
+ *
+ * # Safety
+ */
+void yamlate_string_array_free(const char **array, int32_t length);
+
+/**
+ * Parses a document and returns a handle to its root
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_create_from_string(const char *s);
+
+/**
+ * Copies a YAML value (like one returned by yaml_create_from_string
+ * or yaml_evaluate) into a new document and returns a handle to its root
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_from_yaml(const Yaml *yaml);
+
+/**
+ * Returns the value of a handle, it can be passed to any function that takes a
+ * const YAML pointer (yaml_type, yaml_string_get, yaml_evaluate, etc.) but must
+ * not be destroyed and is only valid while the handle is alive
+ *
+ * # Safety
+ */
+const Yaml *yaml_node_yaml(const YamlNode *node);
+
+/**
+ * Returns a handle to a value of a hash without copying it
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_hash_get(const YamlNode *node, const char *key);
+
+/**
+ * Returns a handle to the key of the entry of a hash at an index
+ * (any YAML value, not only strings) without copying it
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_hash_key_at(const YamlNode *node, int32_t index);
+
+/**
+ * Returns a handle to the value of the entry of a hash at an index without copying it
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_hash_value_at(const YamlNode *node, int32_t index);
+
+/**
+ * Returns handles to the key and the value of every entry of a hash in the order
+ * of the document, the entries and their handles are destroyed with
+ * yaml_node_entries_destroy
+ *
+ * Walking a hash with yaml_node_hash_key_at and yaml_node_hash_value_at
+ * looks up every index from the start of the hash, this takes one pass
+ *
+ * # Safety
+ */
+FFIArrayReturnValue_NodeEntry yaml_node_hash_entries(const YamlNode *node);
+
+/**
+ * Destroys the entries returned by yaml_node_hash_entries and their handles
+ *
+ * # Safety
+ */
+void yaml_node_entries_destroy(FFINodeEntry *entries, int32_t length);
+
+/**
+ * Returns a handle to an element of an array without copying it
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_array_get(const YamlNode *node, int32_t index);
+
+/**
+ * Returns a handle to the value at a path ("cricket.wing_color", "bugs[3].name")
+ * without copying it, fails with InvalidArgument if the path cannot be parsed
+ *
+ * # Safety
+ */
+FFIReturnValue_YamlNode yaml_node_get_path(const YamlNode *node, const char *path);
+
+/**
+ * Evaluates the value of a handle, the result is a new YAML
+ * value that must be destroyed with yaml_destroy
+ *
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_node_evaluate(const YamlNode *node, ASTEnvironment *env);
+
+/**
+ * Destroys a handle, the document is freed when its last handle is destroyed,
+ * does nothing if the handle is null
+ *
+ * # Safety
+ */
+void yaml_node_destroy(const YamlNode *node);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_create_from_string(const char *s);
+
+/**
+ * Does nothing if the YAML value is null
+ *
+ * # Safety
+ */
+void yaml_destroy(Yaml *yaml);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_evaluate(const Yaml *yaml, ASTEnvironment *env);
+
+/**
+ * Returns the value at a path of the document ("cricket.wing_color", "bugs[3].name"),
+ * fails with NotDefined if it is missing and InvalidArgument if the path cannot be parsed
+ *
+ * The value is borrowed from the document, not copied: it must not be
+ * destroyed and it can only be used while the document is alive
+ *
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_get_path(const Yaml *yaml, const char *path);
+
+/**
+ * Evaluates the value at a path of the document without copying
+ * the parts of the document leading to it
+ *
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_evaluate_path(const Yaml *yaml, const char *path, ASTEnvironment *env);
+
+/**
+ * Evaluates the value at paths[i] of the document in envs[i] for every i
+ * returning the results in one array (the value of an item is null and its
+ * error is set when its path is not found or evaluating it fails)
+ * the array must be freed with yaml_batch_destroy
+ *
+ * # Safety
+ */
+FFIArrayReturnValue_Yaml yaml_evaluate_batch(const Yaml *yaml,
+                                             const char *const *paths,
+                                             ASTEnvironment *const *envs,
+                                             int32_t length);
+
+/**
+ * Frees the results returned by yaml_evaluate_batch and the YAML values in them
+ *
+ * # Safety
+ */
+void yaml_batch_destroy(FFIReturnValue_Yaml *results, int32_t length);
+
+/**
+ * # Safety
+ */
+int32_t yaml_type(const Yaml *yaml);
+
+/**
+ * Fails with WrongType if the integer does not fit in 32 bits,
+ * yaml_integer64_get returns every integer
+ *
+ * # Safety
+ */
+FFIReturnValue_i32 yaml_integer_get(const Yaml *yaml);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_i64 yaml_integer64_get(const Yaml *yaml);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_f64 yaml_decimal_get(const Yaml *yaml);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_bool yaml_boolean_get(const Yaml *yaml);
+
+/**
+ * Returns true if the value is null (a missing value like the
+ * result of a statement that returns nothing is null too)
+ *
+ * # Safety
+ */
+FFIReturnValue_bool yaml_is_null(const Yaml *yaml);
+
+/**
+ * The returned string (an empty string on error, null if the YAML value is null)
+ * must be freed with yamlate_string_free
+ *
+ * # Safety
+ */
+FFIReturnValue_String yaml_string_get(const Yaml *yaml);
+
+/**
+ * Returns a value (like the result of an evaluation) as JSON so it can be decoded
+ * in one call, the string must be freed with yamlate_string_free
+ *
+ * Hash keys that are not strings are written as the JSON of the key inside a string
+ * and reals that are not finite are written as null
+ *
+ * # Safety
+ */
+const char *yaml_to_json(const Yaml *yaml);
+
+/**
+ * Returns the string keys of a hash, the array
+ * must be freed with yamlate_string_array_free
+ *
+ * Keys that are not strings are left out, yaml_hash_key_at
+ * returns every key
+ *
+ * # Safety
+ */
+FFIArrayReturnValue_String yaml_hash_keys(const Yaml *yaml);
+
+/**
+ * Returns a copy of a value of a hash that must be destroyed with yaml_destroy
+ *
+ * Deprecated: the value and everything in it is copied on every call.
+ * yaml_node_hash_get returns a handle to the value without copying it
+ *
+ * # Safety
+ */
+YAMLATE_DEPRECATED("copies the value, use yaml_node_hash_get")
+FFIReturnValue_Yaml yaml_hash_get(const Yaml *yaml,
+                                  const char *key);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_i32 yaml_hash_len(const Yaml *yaml);
+
+/**
+ * Returns a copy of the key of the entry of a hash at an index (any YAML value,
+ * not only strings) that must be destroyed with yaml_destroy
+ *
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_hash_key_at(const Yaml *yaml, int32_t index);
+
+/**
+ * Returns a copy of the value of the entry of a hash at an index
+ * that must be destroyed with yaml_destroy
+ *
+ * # Safety
+ */
+FFIReturnValue_Yaml yaml_hash_value_at(const Yaml *yaml, int32_t index);
+
+/**
+ * # Safety
+ */
+FFIReturnValue_i32 yaml_array_len(const Yaml *yaml);
+
+/**
+ * Returns a copy of an element of an array that must be destroyed with yaml_destroy
+ *
+ * Deprecated: the element and everything in it is copied on every call.
+ * yaml_node_array_get returns a handle to the element without copying it
+ *
+ * # Safety
+ */
+YAMLATE_DEPRECATED("copies the element, use yaml_node_array_get")
+FFIReturnValue_Yaml yaml_array_get(const Yaml *yaml,
+                                   int32_t index);
+
+/**
+ * Creates a null value, the values created by the yaml_*_create functions
+ * must be destroyed with yaml_destroy unless they are moved into an array
+ * or a hash
+ */
+Yaml *yaml_null_create(void);
+
+Yaml *yaml_boolean_create(bool value);
+
+Yaml *yaml_integer_create(int64_t value);
+
+Yaml *yaml_decimal_create(double value);
+
+/**
+ * Copies a string into a new value, returns null if the string is null
+ *
+ * # Safety
+ */
+Yaml *yaml_string_create(const char *value);
+
+/**
+ * Creates an empty array
+ */
+Yaml *yaml_array_create(void);
+
+/**
+ * Creates an empty hash
+ */
+Yaml *yaml_hash_create(void);
+
+/**
+ * Moves a value to the end of an array, the value must not be used or
+ * destroyed afterwards unless the function fails
+ *
+ * # Safety
+ */
+int32_t yaml_array_push(Yaml *array, Yaml *value);
+
+/**
+ * Moves a key and a value into a hash, replacing the value of an equal key
+ * (the key can be any value, not only a string), the key and the value must not
+ * be used or destroyed afterwards unless the function fails
+ *
+ * # Safety
+ */
+int32_t yaml_hash_insert(Yaml *hash, Yaml *key, Yaml *value);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* YAMLATE_H */
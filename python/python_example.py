@@ -1,19 +1,14 @@
-import ctypes
-import os
-import ffi_types
-from python_ffi import Yamlate
-
 """
-Python code that tests the C FFI integration
+Python code that tests the C FFI integration with the low level bindings
 Need to run cargo build --release before running this file
 You also need to be in the python/ directory
 """
 
-# load correct library depending on operating system
-if os.uname()[0] == "Darwin":
-    lib = ctypes.cdll.LoadLibrary("../target/release/libyamlate.dylib")
-else:
-    lib = ctypes.cdll.LoadLibrary("../target/release/libyamlate.so")
+from yamlate import ffi_types
+from yamlate.library import load
+from yamlate.python_ffi import Yamlate
+
+lib = load()
 yamlate = Yamlate(lib)
 
 with yamlate.new_environment() as environment:
@@ -1,44 +1,19 @@
-import ctypes
-import os
-from python_ffi import Yamlate
-
 """
-Python code that tests the C FFI integration for the sample bug YAML
+Evaluates the sample bug YAML with the Python package
 Need to run cargo build --release before running this file
-You also need to be in the python/ directory
 """
 
-# load correct library depending on operating system
-if os.uname()[0] == "Darwin":
-    lib = ctypes.cdll.LoadLibrary("../target/release/libyamlate.dylib")
-else:
-    lib = ctypes.cdll.LoadLibrary("../target/release/libyamlate.so")
-
-yamlate = Yamlate(lib)
-
-with yamlate.new_environment() as environment:
-    environment.set_integer(b"another_beetle_nearby", 1)
-    environment.set_string(b"current_season", b"spring")
-
-    with open("../examples/bug.yaml", "r") as yaml_file:
-        data = yaml_file.read().encode("utf-8")
+import os
+import yamlate
 
-        with yamlate.new_yaml_from_str(data) as root_yaml:
-            with (
-                root_yaml.hash_get(b"cricket") as cricket_yaml,
-                cricket_yaml.hash_get(b"wing_color") as wing_yaml,
-                wing_yaml.evaluate(environment) as cricket_wing_result,
-            ):
-                # should print 'red'
-                print(cricket_wing_result.get_string())
+examples = os.path.join(os.path.dirname(os.path.abspath(__file__)), "..", "examples")
 
-            with (
-                root_yaml.hash_get(b"beetle") as beetle_yaml,
-                beetle_yaml.hash_get(b"wing_color") as wing_yaml,
-                wing_yaml.evaluate(environment) as beetle_wing_result,
-            ):
-                # should print 'blue'
-                print(beetle_wing_result.get_string())
+env = yamlate.Environment({"another_beetle_nearby": 1, "current_season": "spring"})
+doc = yamlate.Document.from_file(os.path.join(examples, "bug.yaml"))
 
-                # should print '0'
-                print(environment.get_integer(b"another_beetle_nearby"))
+# should print 'red'
+print(doc["cricket"]["wing_color"].evaluate(env))
+# should print 'blue'
+print(doc["beetle"]["wing_color"].evaluate(env))
+# should print '0'
+print(env["another_beetle_nearby"])
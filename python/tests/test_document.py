@@ -0,0 +1,96 @@
+import json
+import os
+import pytest
+import yamlate
+
+EXAMPLES = os.path.join(os.path.dirname(__file__), "..", "..", "examples")
+
+
+def test_bug_example():
+    doc = yamlate.Document.from_file(os.path.join(EXAMPLES, "bug.yaml"))
+    env = yamlate.Environment({"current_season": "spring", "another_beetle_nearby": 1})
+
+    assert doc["cricket"]["wing_color"].evaluate(env) == "red"
+    assert doc.evaluate_path("beetle.wing_color", env) == "blue"
+    # the expression assigned to the variable
+    assert env["another_beetle_nearby"] == 0
+    assert doc["beetle"]["wing_span"].value == 2.9
+
+
+def test_values():
+    doc = yamlate.Document(
+        """
+        bugs:
+          - name: beetle
+            legs: 6
+            big: 8589934592
+            shiny: true
+            mate: ~
+        1: one
+        [2, 3]: pair
+        """
+    )
+
+    bug = doc["bugs"][0]
+    assert bug.value == {
+        "name": "beetle",
+        "legs": 6,
+        "big": 8589934592,
+        "shiny": True,
+        "mate": None,
+    }
+    assert list(bug) == ["name", "legs", "big", "shiny", "mate"]
+    assert doc["bugs"][-1]["legs"].value == 6
+    assert len(doc["bugs"]) == 1
+    assert doc.get_path("bugs[0].name").value == "beetle"
+    assert doc.value[1] == "one"
+    assert doc.value[(2, 3)] == "pair"
+
+    with pytest.raises(KeyError):
+        doc["ants"]
+    with pytest.raises(IndexError):
+        doc["bugs"][1]
+    with pytest.raises(yamlate.WrongTypeError):
+        doc["bugs"]["name"]
+    with pytest.raises(yamlate.InvalidArgumentError):
+        doc.get_path("bugs[0")
+
+
+def test_json():
+    doc = yamlate.Document(
+        """
+        bugs:
+          - name: '~> "bee" + "tle"'
+            legs: [6, 2.5, true, ~]
+        1: one
+        """
+    )
+
+    assert json.loads(doc.to_json()) == {
+        "bugs": [{"name": '~> "bee" + "tle"', "legs": [6, 2.5, True, None]}],
+        "1": "one",
+    }
+    env = yamlate.Environment()
+    assert doc["bugs"][0]["name"].evaluate_json(env) == "beetle"
+
+
+def test_nodes_outlive_document():
+    doc = yamlate.Document("cricket:\n  legs: '~> pairs * 2'\n")
+    legs = doc["cricket"]["legs"]
+    del doc
+
+    assert legs.evaluate(yamlate.Environment({"pairs": 3})) == 6
+
+
+def test_errors():
+    with pytest.raises(yamlate.ParseError):
+        yamlate.Document("cricket: [1")
+
+    doc = yamlate.Document("cricket:\n  legs: '~> pairs * 2'\n")
+    with pytest.raises(yamlate.EvaluationError) as error:
+        doc.evaluate_path("cricket.legs", yamlate.Environment())
+    assert error.value.path == b"cricket.legs"
+    assert "pairs" in str(error.value)
+
+    with pytest.raises(yamlate.NotDefinedError):
+        doc.evaluate_path("cricket.wings", yamlate.Environment())
@@ -0,0 +1,55 @@
+import pytest
+import yamlate
+
+
+def test_dict_interface():
+    env = yamlate.Environment({"season": "spring", "legs": 6})
+    env["span"] = 2.5
+    env["nocturnal"] = True
+    env["mate"] = None
+
+    assert env["season"] == "spring"
+    assert env["legs"] == 6
+    assert env["span"] == 2.5
+    assert env["nocturnal"] is True
+    assert env["mate"] is None
+    assert "season" in env
+    assert "wings" not in env
+    assert len(env) == 5
+    assert sorted(env) == ["legs", "mate", "nocturnal", "season", "span"]
+    assert env.get("wings", 4) == 4
+
+    del env["season"]
+    assert "season" not in env
+    with pytest.raises(KeyError):
+        env["season"]
+    with pytest.raises(KeyError):
+        del env["season"]
+
+
+def test_bad_values():
+    env = yamlate.Environment()
+    with pytest.raises(OverflowError):
+        env["big"] = 2**40
+    with pytest.raises(TypeError):
+        env["wings"] = [1, 2]
+
+
+def test_copy_and_serialize():
+    env = yamlate.Environment({"legs": 6})
+    env.set_const("species", "cricket")
+
+    copy = env.copy()
+    copy["legs"] = 8
+    assert env["legs"] == 6
+
+    loaded = yamlate.Environment.from_str(env.to_json())
+    assert dict(loaded) == {"legs": 6, "species": "cricket"}
+
+    with pytest.raises(yamlate.ParseError):
+        yamlate.Environment.from_str("legs: [6")
+
+
+def test_context_manager():
+    with yamlate.Environment({"legs": 6}) as env:
+        assert env["legs"] == 6
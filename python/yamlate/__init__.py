@@ -0,0 +1,41 @@
+"""
+Python bindings for Yamlate, evaluates YAML documents with
+embedded expressions given variable bindings
+
+    import yamlate
+
+    env = yamlate.Environment({"current_season": "spring"})
+    doc = yamlate.Document.from_file("bug.yaml")
+    doc["cricket"]["wing_color"].evaluate(env)  # 'red'
+"""
+
+from .document import Document, Node
+from .environment import Environment
+from .errors import (
+    EvaluationError,
+    InvalidArgumentError,
+    InvalidStringError,
+    NotDefinedError,
+    NullPointerError,
+    PanicError,
+    ParseError,
+    WrongTypeError,
+    YamlateError,
+)
+from .library import load
+
+__all__ = [
+    "Document",
+    "Environment",
+    "EvaluationError",
+    "InvalidArgumentError",
+    "InvalidStringError",
+    "Node",
+    "NotDefinedError",
+    "NullPointerError",
+    "PanicError",
+    "ParseError",
+    "WrongTypeError",
+    "YamlateError",
+    "load",
+]
@@ -0,0 +1,236 @@
+"""
+YAML documents whose values can be indexed like dicts and lists and evaluated
+"""
+
+from __future__ import annotations
+import json
+import os
+import weakref
+from collections.abc import Iterator
+from typing import Any
+from . import ffi_types, library
+from .environment import Environment, encode
+from .errors import NotDefinedError, handle_ffi_error
+
+
+def check(lib, result):
+    """
+    Returns the value of a result or raises its error
+    """
+    if result.error != ffi_types.ErrorCode.ERROR_NONE:
+        handle_ffi_error(lib, result.error)
+    return result.value
+
+
+def node_to_python(lib, node) -> Any:
+    """
+    Converts the value of a document handle to Python values
+    (hashes become dicts and arrays lists)
+    """
+    yaml = lib.yaml_node_yaml(node)
+    yaml_type = lib.yaml_type(yaml)
+
+    if yaml_type == ffi_types.YamlType.ARRAY:
+        items = []
+        for i in range(check(lib, lib.yaml_array_len(yaml))):
+            items.append(child_to_python(lib, lib.yaml_node_array_get(node, i)))
+        return items
+    elif yaml_type == ffi_types.YamlType.HASH:
+        h = {}
+        for key, value in hash_entries(lib, node):
+            key = node_to_python(lib, key)
+            # arrays are used as keys in YAML but lists cannot be keys in Python
+            if isinstance(key, list):
+                key = tuple(key)
+            h[key] = node_to_python(lib, value)
+        return h
+    elif yaml_type == ffi_types.YamlType.INTEGER:
+        return check(lib, lib.yaml_integer64_get(yaml))
+    elif yaml_type == ffi_types.YamlType.REAL:
+        return check(lib, lib.yaml_decimal_get(yaml))
+    elif yaml_type == ffi_types.YamlType.BOOLEAN:
+        return check(lib, lib.yaml_boolean_get(yaml))
+    elif yaml_type == ffi_types.YamlType.STRING:
+        result = lib.yaml_string_get(yaml)
+        s = ffi_types.take_string(lib, result.value)
+        check(lib, result)
+        return s.decode("utf-8")
+    return None
+
+
+def hash_entries(lib, node) -> Iterator[tuple[Any, Any]]:
+    """
+    Yields the key and value handles of each entry of a hash in one pass,
+    the handles are destroyed once the iteration ends
+    """
+    entries = lib.yaml_node_hash_entries(node)
+    check(lib, entries)
+    try:
+        for i in range(entries.length):
+            yield entries.value[i].key, entries.value[i].value
+    finally:
+        lib.yaml_node_entries_destroy(entries.value, entries.length)
+
+
+def child_to_python(lib, result) -> Any:
+    node = check(lib, result)
+    try:
+        return node_to_python(lib, node)
+    finally:
+        lib.yaml_node_destroy(node)
+
+
+def yaml_to_python(lib, yaml) -> Any:
+    """
+    Converts a YAML value owned by the caller to Python values and destroys it
+    """
+    try:
+        node = check(lib, lib.yaml_node_from_yaml(yaml))
+    finally:
+        lib.yaml_destroy(yaml)
+    try:
+        return node_to_python(lib, node)
+    finally:
+        lib.yaml_node_destroy(node)
+
+
+class Node:
+    """
+    A value inside a document, indexing it with a key or an index returns
+    the child without copying it:
+
+        wing_color = doc["cricket"]["wing_color"]
+        wing_color.evaluate(env)
+
+    The document is kept alive as long as a node into it is
+    """
+
+    def __init__(self, lib, node):
+        self.lib = lib
+        self.node = node
+        self._finalizer = weakref.finalize(self, lib.yaml_node_destroy, node)
+
+    def close(self) -> None:
+        """
+        Destroys the handle (the document is freed with its last handle)
+        """
+        self._finalizer()
+
+    def __enter__(self) -> Node:
+        return self
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.close()
+        return False
+
+    @property
+    def yaml(self):
+        return self.lib.yaml_node_yaml(self.node)
+
+    def type(self) -> int:
+        """
+        Returns the ffi_types.YamlType of the value
+        """
+        return self.lib.yaml_type(self.yaml)
+
+    def __getitem__(self, key: str | int) -> Node:
+        if isinstance(key, int):
+            if key < 0:
+                key += len(self)
+            result = self.lib.yaml_node_array_get(self.node, key)
+            missing = IndexError
+        else:
+            result = self.lib.yaml_node_hash_get(self.node, encode(key))
+            missing = KeyError
+        try:
+            return Node(self.lib, check(self.lib, result))
+        except NotDefinedError:
+            raise missing(key) from None
+
+    def __len__(self) -> int:
+        if self.type() == ffi_types.YamlType.HASH:
+            return check(self.lib, self.lib.yaml_hash_len(self.yaml))
+        return check(self.lib, self.lib.yaml_array_len(self.yaml))
+
+    def __iter__(self) -> Iterator[Any]:
+        """
+        Iterates over the keys of a hash (like a dict) or the elements of an array
+        """
+        if self.type() == ffi_types.YamlType.HASH:
+            for key, _ in hash_entries(self.lib, self.node):
+                yield node_to_python(self.lib, key)
+        else:
+            for i in range(len(self)):
+                yield self[i]
+
+    def get_path(self, path: str) -> Node:
+        """
+        Returns the value at a path like "cricket.wing_color" or "bugs[3].name",
+        raises InvalidArgumentError if the path cannot be parsed
+        """
+        result = self.lib.yaml_node_get_path(self.node, encode(path))
+        return Node(self.lib, check(self.lib, result))
+
+    @property
+    def value(self) -> Any:
+        """
+        The value converted to Python values without evaluating it
+        """
+        return node_to_python(self.lib, self.node)
+
+    def evaluate(self, env: Environment) -> Any:
+        """
+        Evaluates the value, the result is converted to Python values
+        """
+        result = self.lib.yaml_node_evaluate(self.node, env.environment)
+        return yaml_to_python(self.lib, check(self.lib, result))
+
+    def to_json(self) -> str:
+        """
+        Returns the value as JSON without evaluating it (hash keys that are not
+        strings are written as strings)
+        """
+        s = self.lib.yaml_to_json(self.yaml)
+        return ffi_types.take_string(self.lib, s).decode("utf-8")
+
+    def evaluate_json(self, env: Environment) -> Any:
+        """
+        Evaluates the value and decodes the result with the json module in one call
+        instead of walking it (hash keys are always strings)
+        """
+        result = check(self.lib, self.lib.yaml_node_evaluate(self.node, env.environment))
+        try:
+            s = self.lib.yaml_to_json(result)
+        finally:
+            self.lib.yaml_destroy(result)
+        return json.loads(ffi_types.take_string(self.lib, s))
+
+    def evaluate_path(self, path: str, env: Environment) -> Any:
+        """
+        Evaluates the value at a path like "cricket.wing_color",
+        an error raised while evaluating it has the path
+        """
+        result = self.lib.yaml_evaluate_path(self.yaml, encode(path), env.environment)
+        return yaml_to_python(self.lib, check(self.lib, result))
+
+    def __repr__(self) -> str:
+        return "Node(%r)" % (self.value,)
+
+
+class Document(Node):
+    """
+    A parsed YAML document
+
+        doc = Document(open("bug.yaml").read())
+        doc["cricket"]["wing_color"].evaluate(env)
+    """
+
+    def __init__(self, source: str | bytes, *, lib=None):
+        lib = lib or library.load()
+        result = lib.yaml_node_create_from_string(encode(source))
+        super().__init__(lib, check(lib, result))
+
+    @classmethod
+    def from_file(cls, path: str | os.PathLike, *, lib=None) -> Document:
+        with open(path, "rb") as f:
+            return cls(f.read(), lib=lib)
@@ -0,0 +1,192 @@
+"""
+An environment of variables that behaves like a dict
+"""
+
+from __future__ import annotations
+import weakref
+from collections.abc import Iterator, MutableMapping
+from typing import Any
+from . import ffi_types, library
+from .errors import NotDefinedError, handle_ffi_error
+
+INT_MIN = -(2**31)
+INT_MAX = 2**31 - 1
+
+
+def encode(s: str | bytes) -> bytes:
+    return s.encode("utf-8") if isinstance(s, str) else s
+
+
+class Environment(MutableMapping):
+    """
+    The variables an evaluation reads and assigns
+
+    Values are None, booleans, integers (32 bits), floats and strings:
+
+        env = Environment({"current_season": "spring"})
+        env["another_beetle_nearby"] = 1
+        del env["current_season"]
+
+    The environment is freed when it is garbage collected or closed
+    (it can be used as a context manager)
+    """
+
+    def __init__(self, variables: dict[str, Any] | None = None, *, lib=None):
+        self.lib = lib or library.load()
+        self._own(self.lib.environment_create())
+        if variables:
+            self.update(variables)
+
+    @classmethod
+    def from_str(cls, s: str | bytes, *, lib=None) -> Environment:
+        """
+        Loads an environment saved with to_yaml or to_json or
+        a mapping of variable names to values
+        """
+        lib = lib or library.load()
+        result = lib.environment_create_from_string(encode(s))
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(lib, result.error)
+        return cls._wrap(lib, result.value)
+
+    @classmethod
+    def _wrap(cls, lib, environment) -> Environment:
+        env = cls.__new__(cls)
+        env.lib = lib
+        env._own(environment)
+        return env
+
+    def _own(self, environment):
+        self.environment = environment
+        self._finalizer = weakref.finalize(
+            self, self.lib.environment_destroy, environment
+        )
+
+    def close(self) -> None:
+        self._finalizer()
+
+    def __enter__(self) -> Environment:
+        return self
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.close()
+        return False
+
+    def _check(self, code: int) -> None:
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def __getitem__(self, key: str) -> Any:
+        name = encode(key)
+        result = self.lib.environment_type_of(self.environment, name)
+        if result.error == ffi_types.ErrorCode.ERROR_NOTDEFINED:
+            raise KeyError(key)
+        self._check(result.error)
+
+        if result.value == ffi_types.YamlType.NULL:
+            return None
+        elif result.value == ffi_types.YamlType.STRING:
+            value = self.lib.environment_get_string(self.environment, name)
+            s = ffi_types.take_string(self.lib, value.value)
+            self._check(value.error)
+            return s.decode("utf-8")
+
+        getter = {
+            ffi_types.YamlType.INTEGER: self.lib.environment_get_integer,
+            ffi_types.YamlType.REAL: self.lib.environment_get_decimal,
+            ffi_types.YamlType.BOOLEAN: self.lib.environment_get_boolean,
+        }[result.value]
+        value = getter(self.environment, name)
+        self._check(value.error)
+        return value.value
+
+    def _set(self, key: str, value: Any, constant: bool) -> None:
+        name = encode(key)
+        lib = self.lib
+        if value is None:
+            setter = lib.environment_set_const_null if constant else lib.environment_set_null
+            self._check(setter(self.environment, name))
+            return
+        # bool is checked before int because it is a subclass of int
+        elif isinstance(value, bool):
+            setter = (
+                lib.environment_set_const_boolean
+                if constant
+                else lib.environment_set_boolean
+            )
+        elif isinstance(value, int):
+            if not INT_MIN <= value <= INT_MAX:
+                raise OverflowError("%d does not fit in 32 bits" % value)
+            setter = (
+                lib.environment_set_const_integer
+                if constant
+                else lib.environment_set_integer
+            )
+        elif isinstance(value, float):
+            setter = (
+                lib.environment_set_const_decimal
+                if constant
+                else lib.environment_set_decimal
+            )
+        elif isinstance(value, (str, bytes)):
+            value = encode(value)
+            setter = (
+                lib.environment_set_const_string
+                if constant
+                else lib.environment_set_string
+            )
+        else:
+            raise TypeError(
+                "Cannot store a %s in an environment" % type(value).__name__
+            )
+        self._check(setter(self.environment, name, value))
+
+    def __setitem__(self, key: str, value: Any) -> None:
+        self._set(key, value, False)
+
+    def set_const(self, key: str, value: Any) -> None:
+        """
+        Sets a variable that expressions cannot assign to
+        """
+        self._set(key, value, True)
+
+    def __delitem__(self, key: str) -> None:
+        try:
+            self._check(self.lib.environment_remove(self.environment, encode(key)))
+        except NotDefinedError:
+            raise KeyError(key) from None
+
+    def _keys(self) -> list[str]:
+        result = self.lib.environment_keys(self.environment)
+        keys = ffi_types.take_string_array(self.lib, result)
+        self._check(result.error)
+        return [key.decode("utf-8") for key in keys]
+
+    def __iter__(self) -> Iterator[str]:
+        return iter(self._keys())
+
+    def __len__(self) -> int:
+        return len(self._keys())
+
+    def __repr__(self) -> str:
+        return "Environment(%r)" % dict(self)
+
+    def copy(self) -> Environment:
+        """
+        Returns an independent copy of the environment
+        """
+        return Environment._wrap(self.lib, self.lib.environment_clone(self.environment))
+
+    def set_lenient(self, lenient: bool) -> None:
+        """
+        Makes assigning to an undeclared variable do nothing instead of failing
+        """
+        self._check(self.lib.environment_set_lenient(self.environment, lenient))
+
+    def to_yaml(self) -> str:
+        s = self.lib.environment_to_yaml(self.environment)
+        return ffi_types.take_string(self.lib, s).decode("utf-8")
+
+    def to_json(self) -> str:
+        s = self.lib.environment_to_json(self.environment)
+        return ffi_types.take_string(self.lib, s).decode("utf-8")
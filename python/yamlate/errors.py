@@ -0,0 +1,90 @@
+"""
+Exceptions raised by the Yamlate bindings
+"""
+
+from __future__ import annotations
+from . import ffi_types
+
+
+class YamlateError(Exception):
+    """
+    An error returned by the Yamlate FFI API, message is the description
+    of the error from the library and path the path of the document
+    it happened at (None if it did not happen while evaluating a path)
+    """
+
+    default_message = "Error with Yamlate FFI API"
+
+    def __init__(self, message: bytes | None = None, path: bytes | None = None):
+        super().__init__(message)
+        self.message = message
+        self.path = path
+
+    def __str__(self):
+        if self.message is None:
+            return self.default_message
+        message = self.message.decode("utf-8", "replace")
+        if self.path is not None:
+            return "%s (at %s)" % (message, self.path.decode("utf-8", "replace"))
+        return message
+
+
+class WrongTypeError(YamlateError):
+    default_message = "Wrong type error with Yamlate FFI API"
+
+
+class NotDefinedError(YamlateError):
+    default_message = "Not defined error with Yamlate FFI API"
+
+
+class InvalidStringError(YamlateError):
+    default_message = "Invalid string error with Yamlate FFI API"
+
+
+class EvaluationError(YamlateError):
+    default_message = "Error evaluating expression with Yamlate FFI API"
+
+
+class NullPointerError(YamlateError):
+    default_message = "Null pointer passed to Yamlate FFI API"
+
+
+class ParseError(YamlateError):
+    default_message = "Error parsing YAML with Yamlate FFI API"
+
+
+class PanicError(YamlateError):
+    default_message = "Internal error in Yamlate FFI API"
+
+
+class InvalidArgumentError(YamlateError):
+    default_message = "Invalid argument passed to Yamlate FFI API"
+
+
+ERRORS = {
+    ffi_types.ErrorCode.ERROR_WRONGTYPE: WrongTypeError,
+    ffi_types.ErrorCode.ERROR_NOTDEFINED: NotDefinedError,
+    ffi_types.ErrorCode.ERROR_INVALIDSTRING: InvalidStringError,
+    ffi_types.ErrorCode.ERROR_EVALERROR: EvaluationError,
+    ffi_types.ErrorCode.ERROR_NULLPOINTER: NullPointerError,
+    ffi_types.ErrorCode.ERROR_PARSEERROR: ParseError,
+    ffi_types.ErrorCode.ERROR_PANIC: PanicError,
+    ffi_types.ErrorCode.ERROR_INVALIDARGUMENT: InvalidArgumentError,
+}
+
+
+def handle_ffi_error(lib, code):
+    """
+    Raises the exception for an error code with the description
+    of the last error reported by the library on this thread
+    """
+    error = ERRORS.get(code)
+    if error is None:
+        return
+
+    message = path = None
+    if lib.yamlate_last_error_code() == code:
+        message = ffi_types.take_string(lib, lib.yamlate_last_error_message())
+        path = lib.yamlate_last_error_path()
+        path = ffi_types.take_string(lib, path) if path else None
+    raise error(message, path)
@@ -0,0 +1,367 @@
+"""
+Python C FFI types for Yamlate
+"""
+
+import ctypes
+
+
+class ErrorCode:
+    """
+    The returned error code values
+    """
+
+    ERROR_NONE = 0
+    ERROR_WRONGTYPE = -1
+    ERROR_NOTDEFINED = -2
+    ERROR_INVALIDSTRING = -3
+    ERROR_EVALERROR = -4
+    ERROR_NULLPOINTER = -5
+    ERROR_PARSEERROR = -6
+    ERROR_PANIC = -7
+    ERROR_INVALIDARGUMENT = -8
+
+
+class YamlType:
+    """
+    The returned YAML type code values
+    """
+
+    INTEGER = 0
+    REAL = 1
+    STRING = 2
+    BOOLEAN = 3
+    ARRAY = 4
+    HASH = 5
+    NULL = 6
+
+
+def yaml_type_to_str(yaml: int) -> str:
+    if yaml == YamlType.INTEGER:
+        return "Integer"
+    elif yaml == YamlType.REAL:
+        return "Real"
+    elif yaml == YamlType.STRING:
+        return "String"
+    elif yaml == YamlType.BOOLEAN:
+        return "Boolean"
+    elif yaml == YamlType.ARRAY:
+        return "Array"
+    elif yaml == YamlType.HASH:
+        return "Hash"
+    elif yaml == YamlType.NULL:
+        return "Null"
+    else:
+        return "Unknown type"
+
+
+class Environment(ctypes.Structure):
+    pass
+
+
+class Yaml(ctypes.Structure):
+    pass
+
+
+class Snapshot(ctypes.Structure):
+    pass
+
+
+class YamlNode(ctypes.Structure):
+    pass
+
+
+class IntReturnType(ctypes.Structure):
+    _fields_ = [("value", ctypes.c_int), ("error", ctypes.c_int)]
+
+
+# the value is kept as a raw pointer so it can be freed with yamlate_string_free
+# after it is copied (a c_char_p value is converted to bytes, losing the pointer)
+class StringReturnType(ctypes.Structure):
+    _fields_ = [("value", ctypes.c_void_p), ("error", ctypes.c_int)]
+
+
+class Int64ReturnType(ctypes.Structure):
+    _fields_ = [("value", ctypes.c_int64), ("error", ctypes.c_int)]
+
+
+class BoolReturnType(ctypes.Structure):
+    _fields_ = [("value", ctypes.c_bool), ("error", ctypes.c_int)]
+
+
+class DecimalReturnType(ctypes.Structure):
+    _fields_ = [("value", ctypes.c_double), ("error", ctypes.c_int)]
+
+
+class ArrayStringReturnType(ctypes.Structure):
+    _fields_ = [
+        ("value", ctypes.POINTER(ctypes.c_char_p)),
+        ("length", ctypes.c_int),
+        ("error", ctypes.c_int),
+    ]
+
+
+env_p = ctypes.POINTER(Environment)
+yaml_p = ctypes.POINTER(Yaml)
+snapshot_p = ctypes.POINTER(Snapshot)
+node_p = ctypes.POINTER(YamlNode)
+
+
+class YamlReturnType(ctypes.Structure):
+    _fields_ = [("value", yaml_p), ("error", ctypes.c_int)]
+
+
+class YamlNodeReturnType(ctypes.Structure):
+    _fields_ = [("value", node_p), ("error", ctypes.c_int)]
+
+
+class EnvReturnType(ctypes.Structure):
+    _fields_ = [("value", env_p), ("error", ctypes.c_int)]
+
+
+class ArrayYamlReturnType(ctypes.Structure):
+    _fields_ = [
+        ("value", ctypes.POINTER(YamlReturnType)),
+        ("length", ctypes.c_int),
+        ("error", ctypes.c_int),
+    ]
+
+
+class ChangeType(ctypes.Structure):
+    _fields_ = [
+        ("name", ctypes.c_char_p),
+        ("old_value", yaml_p),
+        ("new_value", yaml_p),
+        ("expression", ctypes.c_char_p),
+    ]
+
+
+class ArrayChangeReturnType(ctypes.Structure):
+    _fields_ = [
+        ("value", ctypes.POINTER(ChangeType)),
+        ("length", ctypes.c_int),
+        ("error", ctypes.c_int),
+    ]
+
+
+class NodeEntryType(ctypes.Structure):
+    _fields_ = [("key", node_p), ("value", node_p)]
+
+
+class ArrayNodeEntryReturnType(ctypes.Structure):
+    _fields_ = [
+        ("value", ctypes.POINTER(NodeEntryType)),
+        ("length", ctypes.c_int),
+        ("error", ctypes.c_int),
+    ]
+
+
+def take_string(lib, value) -> bytes:
+    """
+    Copies a string returned by the library and frees it
+    """
+    if not value:
+        return b""
+    s = ctypes.string_at(value)
+    lib.yamlate_string_free(value)
+    return s
+
+
+def take_string_array(lib, result) -> list[bytes]:
+    """
+    Copies an array of strings returned by the library and frees it
+    """
+    if not result.value:
+        return []
+    strings = [result.value[i] for i in range(0, result.length)]
+    lib.yamlate_string_array_free(result.value, result.length)
+    return strings
+
+
+def ffi_function_signatures(lib):
+    """
+    Defines the C FFI function signatures
+    """
+    lib.environment_create.restype = env_p
+    lib.environment_destroy.argtypes = [env_p]
+
+    lib.yamlate_string_free.argtypes = [ctypes.c_void_p]
+    lib.yamlate_string_array_free.argtypes = [
+        ctypes.POINTER(ctypes.c_char_p),
+        ctypes.c_int,
+    ]
+
+    lib.yamlate_last_error_code.restype = ctypes.c_int
+    lib.yamlate_last_error_message.restype = ctypes.c_void_p
+    lib.yamlate_last_error_path.restype = ctypes.c_void_p
+
+    lib.environment_create_from_string.argtypes = [ctypes.c_char_p]
+    lib.environment_create_from_string.restype = EnvReturnType
+    lib.environment_to_yaml.argtypes = [env_p]
+    lib.environment_to_yaml.restype = ctypes.c_void_p
+    lib.environment_to_json.argtypes = [env_p]
+    lib.environment_to_json.restype = ctypes.c_void_p
+
+    lib.environment_clone.argtypes = [env_p]
+    lib.environment_clone.restype = env_p
+
+    lib.environment_snapshot.argtypes = [env_p]
+    lib.environment_snapshot.restype = snapshot_p
+    lib.environment_restore.argtypes = [env_p, snapshot_p]
+    lib.environment_restore.restype = ctypes.c_int
+    lib.environment_commit.argtypes = [env_p, snapshot_p]
+    lib.environment_commit.restype = ctypes.c_int
+
+    lib.environment_set_lenient.argtypes = [env_p, ctypes.c_bool]
+    lib.environment_set_lenient.restype = ctypes.c_int
+
+    lib.environment_set_track_changes.argtypes = [env_p, ctypes.c_bool]
+    lib.environment_set_track_changes.restype = ctypes.c_int
+    lib.environment_changes.argtypes = [env_p]
+    lib.environment_changes.restype = ArrayChangeReturnType
+    lib.environment_changes_destroy.argtypes = [
+        ctypes.POINTER(ChangeType),
+        ctypes.c_int,
+    ]
+    lib.environment_clear_changes.argtypes = [env_p]
+    lib.environment_clear_changes.restype = ctypes.c_int
+
+    lib.environment_set_integer.argtypes = [env_p, ctypes.c_char_p, ctypes.c_int]
+    lib.environment_set_integer.restype = ctypes.c_int
+    lib.environment_get_integer.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_get_integer.restype = IntReturnType
+
+    lib.environment_set_string.argtypes = [env_p, ctypes.c_char_p, ctypes.c_char_p]
+    lib.environment_set_string.restype = ctypes.c_int
+    lib.environment_get_string.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_get_string.restype = StringReturnType
+
+    lib.environment_set_decimal.argtypes = [env_p, ctypes.c_char_p, ctypes.c_double]
+    lib.environment_set_decimal.restype = ctypes.c_int
+    lib.environment_get_decimal.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_get_decimal.restype = DecimalReturnType
+
+    lib.environment_set_boolean.argtypes = [env_p, ctypes.c_char_p, ctypes.c_bool]
+    lib.environment_set_boolean.restype = ctypes.c_int
+    lib.environment_get_boolean.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_get_boolean.restype = BoolReturnType
+    lib.environment_set_null.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_set_null.restype = ctypes.c_int
+
+    lib.environment_keys.argtypes = [env_p]
+    lib.environment_keys.restype = ArrayStringReturnType
+    lib.environment_type_of.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_type_of.restype = IntReturnType
+    lib.environment_remove.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_remove.restype = ctypes.c_int
+
+    lib.environment_set_const_integer.argtypes = [env_p, ctypes.c_char_p, ctypes.c_int]
+    lib.environment_set_const_integer.restype = ctypes.c_int
+    lib.environment_set_const_string.argtypes = [
+        env_p,
+        ctypes.c_char_p,
+        ctypes.c_char_p,
+    ]
+    lib.environment_set_const_string.restype = ctypes.c_int
+    lib.environment_set_const_decimal.argtypes = [
+        env_p,
+        ctypes.c_char_p,
+        ctypes.c_double,
+    ]
+    lib.environment_set_const_decimal.restype = ctypes.c_int
+    lib.environment_set_const_boolean.argtypes = [
+        env_p,
+        ctypes.c_char_p,
+        ctypes.c_bool,
+    ]
+    lib.environment_set_const_boolean.restype = ctypes.c_int
+    lib.environment_set_const_null.argtypes = [env_p, ctypes.c_char_p]
+    lib.environment_set_const_null.restype = ctypes.c_int
+
+    lib.yaml_create_from_string.argtypes = [ctypes.c_char_p]
+    lib.yaml_create_from_string.restype = YamlReturnType
+
+    lib.yaml_destroy.argtypes = [yaml_p]
+
+    lib.yaml_evaluate.argtypes = [yaml_p, env_p]
+    lib.yaml_evaluate.restype = YamlReturnType
+
+    lib.yaml_node_create_from_string.argtypes = [ctypes.c_char_p]
+    lib.yaml_node_create_from_string.restype = YamlNodeReturnType
+    lib.yaml_node_from_yaml.argtypes = [yaml_p]
+    lib.yaml_node_from_yaml.restype = YamlNodeReturnType
+    lib.yaml_node_yaml.argtypes = [node_p]
+    lib.yaml_node_yaml.restype = yaml_p
+    lib.yaml_node_hash_get.argtypes = [node_p, ctypes.c_char_p]
+    lib.yaml_node_hash_get.restype = YamlNodeReturnType
+    lib.yaml_node_array_get.argtypes = [node_p, ctypes.c_int]
+    lib.yaml_node_array_get.restype = YamlNodeReturnType
+    lib.yaml_node_get_path.argtypes = [node_p, ctypes.c_char_p]
+    lib.yaml_node_get_path.restype = YamlNodeReturnType
+    lib.yaml_node_evaluate.argtypes = [node_p, env_p]
+    lib.yaml_node_evaluate.restype = YamlReturnType
+    lib.yaml_node_destroy.argtypes = [node_p]
+
+    lib.yaml_get_path.argtypes = [yaml_p, ctypes.c_char_p]
+    lib.yaml_get_path.restype = YamlReturnType
+    lib.yaml_evaluate_path.argtypes = [yaml_p, ctypes.c_char_p, env_p]
+    lib.yaml_evaluate_path.restype = YamlReturnType
+
+    lib.yaml_evaluate_batch.argtypes = [
+        yaml_p,
+        ctypes.POINTER(ctypes.c_char_p),
+        ctypes.POINTER(env_p),
+        ctypes.c_int,
+    ]
+    lib.yaml_evaluate_batch.restype = ArrayYamlReturnType
+    lib.yaml_batch_destroy.argtypes = [ctypes.POINTER(YamlReturnType), ctypes.c_int]
+
+    lib.yaml_type.argtypes = [yaml_p]
+    lib.yaml_type.restype = ctypes.c_int
+
+    lib.yaml_integer_get.argtypes = [yaml_p]
+    lib.yaml_integer_get.restype = IntReturnType
+    lib.yaml_integer64_get.argtypes = [yaml_p]
+    lib.yaml_integer64_get.restype = Int64ReturnType
+
+    lib.yaml_decimal_get.argtypes = [yaml_p]
+    lib.yaml_decimal_get.restype = DecimalReturnType
+
+    lib.yaml_boolean_get.argtypes = [yaml_p]
+    lib.yaml_boolean_get.restype = BoolReturnType
+    lib.yaml_is_null.argtypes = [yaml_p]
+    lib.yaml_is_null.restype = BoolReturnType
+
+    lib.yaml_string_get.argtypes = [yaml_p]
+    lib.yaml_string_get.restype = StringReturnType
+
+    lib.yaml_to_json.argtypes = [yaml_p]
+    lib.yaml_to_json.restype = ctypes.c_void_p
+
+    lib.yaml_hash_keys.argtypes = [yaml_p]
+    lib.yaml_hash_keys.restype = ArrayStringReturnType
+
+    lib.yaml_hash_get.argtypes = [yaml_p, ctypes.c_char_p]
+    lib.yaml_hash_get.restype = YamlReturnType
+
+    lib.yaml_hash_len.argtypes = [yaml_p]
+    lib.yaml_hash_len.restype = IntReturnType
+    lib.yaml_hash_key_at.argtypes = [yaml_p, ctypes.c_int]
+    lib.yaml_hash_key_at.restype = YamlReturnType
+    lib.yaml_hash_value_at.argtypes = [yaml_p, ctypes.c_int]
+    lib.yaml_hash_value_at.restype = YamlReturnType
+    lib.yaml_node_hash_key_at.argtypes = [node_p, ctypes.c_int]
+    lib.yaml_node_hash_key_at.restype = YamlNodeReturnType
+    lib.yaml_node_hash_value_at.argtypes = [node_p, ctypes.c_int]
+    lib.yaml_node_hash_value_at.restype = YamlNodeReturnType
+    lib.yaml_node_hash_entries.argtypes = [node_p]
+    lib.yaml_node_hash_entries.restype = ArrayNodeEntryReturnType
+    lib.yaml_node_entries_destroy.argtypes = [
+        ctypes.POINTER(NodeEntryType),
+        ctypes.c_int,
+    ]
+
+    lib.yaml_array_len.argtypes = [yaml_p]
+    lib.yaml_array_len.restype = IntReturnType
+
+    lib.yaml_array_get.argtypes = [yaml_p, ctypes.c_int]
+    lib.yaml_array_get.restype = YamlReturnType
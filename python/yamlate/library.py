@@ -0,0 +1,66 @@
+"""
+Finds and loads the Yamlate shared library
+"""
+
+import ctypes
+import ctypes.util
+import os
+import sys
+from . import ffi_types
+
+_lib = None
+
+
+def library_name() -> str:
+    if sys.platform == "darwin":
+        return "libyamlate.dylib"
+    elif sys.platform == "win32":
+        return "yamlate.dll"
+    else:
+        return "libyamlate.so"
+
+
+def find_library() -> str:
+    """
+    Returns the path of the library, looking in order at:
+    the YAMLATE_LIBRARY environment variable, a library copied into the package,
+    the release build of the repository the package is in and the system libraries
+    (installed with make install)
+    """
+    path = os.environ.get("YAMLATE_LIBRARY")
+    if path:
+        return path
+
+    package = os.path.dirname(os.path.abspath(__file__))
+    candidates = [
+        os.path.join(package, library_name()),
+        os.path.join(package, "..", "..", "target", "release", library_name()),
+    ]
+    for candidate in candidates:
+        if os.path.exists(candidate):
+            return candidate
+
+    path = ctypes.util.find_library("yamlate")
+    if path:
+        return path
+
+    raise OSError(
+        "Cannot find the Yamlate library, build it with cargo build --release "
+        "or set YAMLATE_LIBRARY to its path"
+    )
+
+
+def load(path: str | None = None):
+    """
+    Loads the library with the function signatures set, the library found
+    by find_library is loaded once and shared
+    """
+    global _lib
+    if path is None and _lib is not None:
+        return _lib
+
+    lib = ctypes.cdll.LoadLibrary(path or find_library())
+    ffi_types.ffi_function_signatures(lib)
+    if path is None:
+        _lib = lib
+    return lib
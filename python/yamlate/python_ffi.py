@@ -0,0 +1,571 @@
+"""
+Low level bindings for the Yamlate C FFI, every value and environment
+is managed with a context manager and keys and strings are bytes
+
+yamlate.Document and yamlate.Environment wrap these with Python values
+"""
+
+from __future__ import annotations
+import ctypes
+from . import ffi_types
+from .errors import handle_ffi_error
+
+
+class Environment:
+    def __init__(self, lib, environment):
+        self.environment = environment
+        self.lib = lib
+
+    def to_yaml(self) -> bytes:
+        """
+        Returns every scope of the environment as YAML
+        """
+        return ffi_types.take_string(
+            self.lib, self.lib.environment_to_yaml(self.environment)
+        )
+
+    def to_json(self) -> bytes:
+        """
+        Returns every scope of the environment as JSON
+        """
+        return ffi_types.take_string(
+            self.lib, self.lib.environment_to_json(self.environment)
+        )
+
+    def clone(self) -> CopyEnv:
+        """
+        Copies the environment, the copy is freed when leaving the with block
+        """
+        return CopyEnv(self.lib, self.lib.environment_clone(self.environment))
+
+    def snapshot(self):
+        """
+        Starts recording changes so they can be undone with restore
+        or kept with commit
+        """
+        return self.lib.environment_snapshot(self.environment)
+
+    def restore(self, snapshot) -> None:
+        """
+        Undoes every change made since the snapshot was taken
+        :param snapshot: snapshot
+        """
+        code = self.lib.environment_restore(self.environment, snapshot)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def commit(self, snapshot) -> None:
+        """
+        Keeps every change made since the snapshot was taken
+        :param snapshot: snapshot
+        """
+        code = self.lib.environment_commit(self.environment, snapshot)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_lenient(self, lenient: bool) -> None:
+        """
+        Sets whether assigning to an undeclared variable is ignored
+        instead of being an evaluation error
+        :param bool: lenient
+        """
+        code = self.lib.environment_set_lenient(self.environment, lenient)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_track_changes(self, track: bool) -> None:
+        """
+        Turns recording the changes made to variables on or off
+        :param bool: track
+        """
+        code = self.lib.environment_set_track_changes(self.environment, track)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def changes(self) -> ChangeList:
+        """
+        Returns the changes recorded so far, the values are
+        freed when leaving the with block
+        """
+        result = self.lib.environment_changes(self.environment)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return ChangeList(self.lib, result.value, result.length)
+
+    def clear_changes(self) -> None:
+        """
+        Discards the changes recorded so far
+        """
+        code = self.lib.environment_clear_changes(self.environment)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def keys(self) -> list[bytes]:
+        """
+        Returns the names of the variables visible from the current scope
+        """
+        result = self.lib.environment_keys(self.environment)
+        keys = ffi_types.take_string_array(self.lib, result)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return keys
+
+    def type_of(self, key: bytes) -> int:
+        """
+        Returns the type of a variable as a ffi_types.YamlType
+        :param string: key
+        """
+        result = self.lib.environment_type_of(self.environment, key)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def remove(self, key: bytes) -> None:
+        """
+        Removes the most current binding of a variable
+        :param string: key
+        """
+        code = self.lib.environment_remove(self.environment, key)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_integer(self, key: bytes, val: int) -> None:
+        """
+        Sets an integer in the environment
+        :param string: key
+        :param integer: val
+        """
+        code = self.lib.environment_set_integer(self.environment, key, val)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_decimal(self, key: bytes, val: float) -> None:
+        """
+        Sets decimal in the environment
+        :param string: key
+        :param double: val
+        """
+        code = self.lib.environment_set_decimal(self.environment, key, val)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_string(self, key: bytes, val: bytes) -> None:
+        """
+        Sets string in the environment
+        :param string: key
+        :param string: val
+        """
+        code = self.lib.environment_set_string(self.environment, key, val)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_const_integer(self, key: bytes, val: int) -> None:
+        """
+        Sets a read-only integer in the environment
+        :param string: key
+        :param integer: val
+        """
+        code = self.lib.environment_set_const_integer(self.environment, key, val)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_const_decimal(self, key: bytes, val: float) -> None:
+        """
+        Sets a read-only decimal in the environment
+        :param string: key
+        :param double: val
+        """
+        code = self.lib.environment_set_const_decimal(self.environment, key, val)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def set_const_string(self, key: bytes, val: bytes) -> None:
+        """
+        Sets a read-only string in the environment
+        :param string: key
+        :param string: val
+        """
+        code = self.lib.environment_set_const_string(self.environment, key, val)
+        if code != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, code)
+
+    def get_integer(self, key: bytes) -> int:
+        """
+        Gets an integer in the environment
+        :param string: key
+        :return: integer
+        """
+        result = self.lib.environment_get_integer(self.environment, key)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def get_decimal(self, key: bytes) -> float:
+        """
+        Gets an decimal in the environment
+        :param string: key
+        :return: double
+        """
+        result = self.lib.environment_get_decimal(self.environment, key)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def get_string(self, key: bytes) -> bytes:
+        """
+        Gets an string in the environment
+        :param string: key
+        :return: string
+        """
+        result = self.lib.environment_get_string(self.environment, key)
+        value = ffi_types.take_string(self.lib, result.value)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return value
+
+
+class Yaml:
+    def __init__(self, lib, yaml, node=None):
+        """
+        :param yaml: pointer to the YAML value
+        :param node: handle the value was borrowed from, if there is one
+        children are looked up through it instead of copying them
+        """
+        self.yaml = yaml
+        self.lib = lib
+        self.node = node
+
+    def type(self) -> int:
+        return self.lib.yaml_type(self.yaml)
+
+    def evaluate(self, env: Environment) -> CopyYaml:
+        result = self.lib.yaml_evaluate(self.yaml, env.environment)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+        return CopyYaml(self.lib, result.value)
+
+    def get_path(self, path: bytes) -> NodeYaml | BorrowedYaml:
+        """
+        Returns the value at a path like b"cricket.wing_color" or b"bugs[3].name",
+        the value is not copied so unless this YAML is a handle into a document
+        it can only be used while this YAML is alive
+        :param string: path
+        """
+        if self.node:
+            result = self.lib.yaml_node_get_path(self.node, path)
+            if result.error != ffi_types.ErrorCode.ERROR_NONE:
+                handle_ffi_error(self.lib, result.error)
+            return NodeYaml(self.lib, result.value)
+
+        result = self.lib.yaml_get_path(self.yaml, path)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return BorrowedYaml(self.lib, result.value)
+
+    def evaluate_path(self, path: bytes, environment: Environment) -> CopyYaml:
+        """
+        Evaluates the value at a path like b"cricket.wing_color"
+        :param string: path
+        :param environment: environment
+        """
+        result = self.lib.yaml_evaluate_path(self.yaml, path, environment.environment)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return CopyYaml(self.lib, result.value)
+
+    def evaluate_batch(
+        self, paths: list[bytes], environments: list[Environment]
+    ) -> BatchResults:
+        """
+        Evaluates the value at paths[i] in environments[i] for every i in one call,
+        the results are None for items whose path is not found or fail to evaluate
+        and are freed when leaving the with block
+        :param list: paths (like b"cricket.wing_color" or b"bugs[3].name")
+        :param list: environments
+        """
+        length = len(paths)
+        if len(environments) != length:
+            raise ValueError("paths and environments must have the same length")
+
+        c_paths = (ctypes.c_char_p * length)(*paths)
+        c_envs = (ffi_types.env_p * length)(*[env.environment for env in environments])
+        result = self.lib.yaml_evaluate_batch(self.yaml, c_paths, c_envs, length)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return BatchResults(self.lib, result.value, result.length)
+
+    def get_integer(self) -> int:
+        result = self.lib.yaml_integer64_get(self.yaml)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def get_decimal(self):
+        result = self.lib.yaml_decimal_get(self.yaml)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def get_boolean(self) -> bool:
+        result = self.lib.yaml_boolean_get(self.yaml)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def is_null(self) -> bool:
+        result = self.lib.yaml_is_null(self.yaml)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return result.value
+
+    def get_string(self) -> bytes:
+        result = self.lib.yaml_string_get(self.yaml)
+        value = ffi_types.take_string(self.lib, result.value)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return value
+
+    def hash_keys(self) -> list[bytes]:
+        result = self.lib.yaml_hash_keys(self.yaml)
+        keys = ffi_types.take_string_array(self.lib, result)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return keys
+
+    def hash_get(self, key: bytes) -> NodeYaml | CopyYaml:
+        if self.node:
+            result = self.lib.yaml_node_hash_get(self.node, key)
+            if result.error != ffi_types.ErrorCode.ERROR_NONE:
+                handle_ffi_error(self.lib, result.error)
+            return NodeYaml(self.lib, result.value)
+
+        result = self.lib.yaml_hash_get(self.yaml, key)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return CopyYaml(self.lib, result.value)
+
+    def hash_len(self) -> int:
+        result = self.lib.yaml_hash_len(self.yaml)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+        return result.value
+
+    def hash_key_at(self, index: int) -> NodeYaml | CopyYaml:
+        """
+        Returns the key of the entry at an index, keys that are
+        not strings (left out by hash_keys) can only be read this way
+        """
+        if self.node:
+            result = self.lib.yaml_node_hash_key_at(self.node, index)
+            if result.error != ffi_types.ErrorCode.ERROR_NONE:
+                handle_ffi_error(self.lib, result.error)
+            return NodeYaml(self.lib, result.value)
+
+        result = self.lib.yaml_hash_key_at(self.yaml, index)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+        return CopyYaml(self.lib, result.value)
+
+    def hash_value_at(self, index: int) -> NodeYaml | CopyYaml:
+        if self.node:
+            result = self.lib.yaml_node_hash_value_at(self.node, index)
+            if result.error != ffi_types.ErrorCode.ERROR_NONE:
+                handle_ffi_error(self.lib, result.error)
+            return NodeYaml(self.lib, result.value)
+
+        result = self.lib.yaml_hash_value_at(self.yaml, index)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+        return CopyYaml(self.lib, result.value)
+
+    def array_len(self) -> int:
+        result = self.lib.yaml_array_len(self.yaml)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+        return result.value
+
+    def array_get(self, index: int) -> NodeYaml | CopyYaml:
+        if self.node:
+            result = self.lib.yaml_node_array_get(self.node, index)
+            if result.error != ffi_types.ErrorCode.ERROR_NONE:
+                handle_ffi_error(self.lib, result.error)
+            return NodeYaml(self.lib, result.value)
+
+        result = self.lib.yaml_array_get(self.yaml, index)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+        return CopyYaml(self.lib, result.value)
+
+
+class NewEnv:
+    def __init__(self, lib):
+        self.lib = lib
+
+    def __enter__(self):
+        self.environment = self.lib.environment_create()
+        return Environment(self.lib, self.environment)
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.environment_destroy(self.environment)
+        return True
+
+
+class CopyEnv:
+    def __init__(self, lib, environment):
+        self.lib = lib
+        self.environment = environment
+
+    def __enter__(self):
+        return Environment(self.lib, self.environment)
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.environment_destroy(self.environment)
+        return True
+
+
+class Change:
+    """
+    A change made to a variable, old is None if the variable was not declared
+    and expression is None if the change was not made by an expression
+    """
+
+    def __init__(self, lib, change):
+        self.name = change.name
+        self.old = Yaml(lib, change.old_value) if change.old_value else None
+        self.new = Yaml(lib, change.new_value)
+        self.expression = change.expression
+
+
+class ChangeList:
+    def __init__(self, lib, changes, length: int):
+        self.lib = lib
+        self.changes = changes
+        self.length = length
+
+    def __enter__(self):
+        return [Change(self.lib, self.changes[i]) for i in range(0, self.length)]
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.environment_changes_destroy(self.changes, self.length)
+        return True
+
+
+class BorrowedYaml:
+    def __init__(self, lib, yaml):
+        self.lib = lib
+        self.yaml = yaml
+
+    def __enter__(self):
+        return Yaml(self.lib, self.yaml)
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        return True
+
+
+class BatchResults:
+    def __init__(self, lib, results, length: int):
+        self.lib = lib
+        self.results = results
+        self.length = length
+
+    def __enter__(self):
+        return [
+            Yaml(self.lib, self.results[i].value) if self.results[i].value else None
+            for i in range(0, self.length)
+        ]
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.yaml_batch_destroy(self.results, self.length)
+        return True
+
+
+class NewYaml:
+    def __init__(self, lib, s: bytes):
+        self.lib = lib
+        self.yaml_str = s
+
+    def __enter__(self):
+        result = self.lib.yaml_node_create_from_string(self.yaml_str)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        self.node = result.value
+        return Yaml(self.lib, self.lib.yaml_node_yaml(self.node), self.node)
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.yaml_node_destroy(self.node)
+        return True
+
+
+class NodeYaml:
+    """
+    A handle to a value inside a document, the document is
+    freed when its last handle is destroyed
+    """
+
+    def __init__(self, lib, node):
+        self.lib = lib
+        self.node = node
+
+    def __enter__(self):
+        return Yaml(self.lib, self.lib.yaml_node_yaml(self.node), self.node)
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.yaml_node_destroy(self.node)
+        return True
+
+
+class CopyYaml:
+    def __init__(self, lib, yaml):
+        self.lib = lib
+        self.yaml = yaml
+
+    def __enter__(self):
+        return Yaml(self.lib, self.yaml)
+
+    def __exit__(self, ex_type, ex_val, traceback):
+        self.lib.yaml_destroy(self.yaml)
+        return True
+
+
+class Yamlate:
+    """
+    Main class for Yamlate
+    """
+
+    def __init__(self, lib):
+        self.lib = lib
+        ffi_types.ffi_function_signatures(self.lib)
+
+    def new_environment(self) -> NewEnv:
+        return NewEnv(self.lib)
+
+    def new_environment_from_str(self, s: bytes) -> CopyEnv:
+        """
+        Loads an environment saved with to_yaml or to_json or
+        a mapping of variable names to values
+        """
+        result = self.lib.environment_create_from_string(s)
+        if result.error != ffi_types.ErrorCode.ERROR_NONE:
+            handle_ffi_error(self.lib, result.error)
+
+        return CopyEnv(self.lib, result.value)
+
+    def new_yaml_from_str(self, s: bytes) -> NewYaml:
+        return NewYaml(self.lib, s)
@@ -13,6 +13,8 @@ pub enum Exp {
     Variable(String),
     /// Bind a variable name to the evaluated expression
     Declare(String, Box<Exp>),
+    /// Bind a read-only variable name to the evaluated expression
+    DeclareConst(String, Box<Exp>),
     /// Set an existing variable name to the evaluated expression
     Assign(String, Box<Exp>),
     /// A literal like 2 or "hello"
@@ -27,9 +29,10 @@ impl Exp {
                 Some(lit) => Ok(Exp::Lit(lit)),
                 None => Err(YamlError::EvalError(EvalError::VarNotInEnv(name.clone()))),
             },
-            Exp::Declare(ref name, ref exp) => {
+            Exp::Declare(ref name, ref exp) | Exp::DeclareConst(ref name, ref exp) => {
                 if let Exp::Lit(value) = exp.eval(env)? {
-                    env.set(name.as_str(), value.clone());
+                    let constant = matches!(*self, Exp::DeclareConst(..));
+                    declare(env, name, value.clone(), constant)?;
                     Ok(Exp::Lit(value))
                 } else {
                     Err(YamlError::EvalError(EvalError::CannotReduceDeclare(
@@ -39,7 +42,7 @@ impl Exp {
             }
             Exp::Assign(ref name, ref exp) => {
                 if let Exp::Lit(value) = exp.eval(env)? {
-                    env.assign(name.as_str(), value.clone());
+                    assign(env, name, value.clone())?;
                     Ok(Exp::Lit(value))
                 } else {
                     Err(YamlError::EvalError(EvalError::CannotReduceAssign(
@@ -49,20 +52,7 @@ impl Exp {
             }
             Exp::UnaryOp(ref op, ref exp) => {
                 if let Exp::Lit(value) = exp.eval(env)? {
-                    match *op {
-                        Op::Not => Ok(Exp::Lit((!value)?)),
-                        // Non-unary operators (for exhaustiveness checking)
-                        Op::Plus
-                        | Op::Minus
-                        | Op::Times
-                        | Op::Divide
-                        | Op::Modulo
-                        | Op::Exponent
-                        | Op::And
-                        | Op::Or
-                        | Op::Equal
-                        | Op::NotEqual => Err(YamlError::EvalError(EvalError::NotUnOp(*op))),
-                    }
+                    Ok(Exp::Lit(value.apply_unary(*op)?))
                 } else {
                     Err(YamlError::EvalError(EvalError::CannotReduceUnOp(
                         *op,
@@ -72,20 +62,7 @@ impl Exp {
             }
             Exp::BinaryOp(ref op, ref exp1, ref exp2) => {
                 if let (Exp::Lit(val1), Exp::Lit(val2)) = (exp1.eval(env)?, exp2.eval(env)?) {
-                    Ok(Exp::Lit(match *op {
-                        Op::Plus => (val1 + val2)?,
-                        Op::Minus => (val1 - val2)?,
-                        Op::Times => (val1 * val2)?,
-                        Op::Divide => (val1 / val2)?,
-                        Op::Modulo => (val1 % val2)?,
-                        Op::Exponent => val1.exp(val2)?,
-                        Op::And => val1.and(val2)?,
-                        Op::Or => val1.or(val2)?,
-                        Op::Equal => Lit::Bool(val1 == val2),
-                        Op::NotEqual => Lit::Bool(val1 != val2),
-                        // Non-binary operators (for exhaustiveness checking)
-                        Op::Not => return Err(YamlError::EvalError(EvalError::NotBinOp(*op))),
-                    }))
+                    Ok(Exp::Lit(val1.apply_binary(*op, val2)?))
                 } else {
                     Err(YamlError::EvalError(EvalError::CannotReduceBinOp(
                         *op,
@@ -99,10 +76,115 @@ impl Exp {
     }
 }
 
+/// The environment operations declaring and assigning a variable need,
+/// with variables referred to by K (a name for any environment and a symbol
+/// for a slot environment) so every evaluator goes through declare_var and assign_var
+pub(crate) trait Bindings<K: Copy> {
+    fn name(&self, var: K) -> String;
+    fn is_const(&self, var: K) -> bool;
+    fn is_const_in_scope(&self, var: K) -> bool;
+    /// Binds the variable in the current scope, returns false if it cannot be bound
+    fn bind(&mut self, var: K, value: Lit, constant: bool) -> bool;
+    fn assign(&mut self, var: K, value: Lit) -> bool;
+    fn is_lenient(&self) -> bool;
+}
+
+impl<'a, E: Environment + ?Sized> Bindings<&'a str> for E {
+    fn name(&self, var: &'a str) -> String {
+        var.to_owned()
+    }
+
+    fn is_const(&self, var: &'a str) -> bool {
+        Environment::is_const(self, var)
+    }
+
+    fn is_const_in_scope(&self, var: &'a str) -> bool {
+        Environment::is_const_in_scope(self, var)
+    }
+
+    fn bind(&mut self, var: &'a str, value: Lit, constant: bool) -> bool {
+        if constant {
+            self.set_const(var, value);
+        } else {
+            self.set(var, value);
+        }
+        true
+    }
+
+    fn assign(&mut self, var: &'a str, value: Lit) -> bool {
+        Environment::assign(self, var, value)
+    }
+
+    fn is_lenient(&self) -> bool {
+        Environment::is_lenient(self)
+    }
+}
+
+/// Binds a variable to a value in the current scope of the environment
+/// returns an error if the variable is bound to a read-only value in the current scope
+/// (like the host's set, a declaration in an inner scope shadows it)
+pub(crate) fn declare_var<K: Copy>(
+    env: &mut (impl Bindings<K> + ?Sized),
+    var: K,
+    value: Lit,
+    constant: bool,
+) -> Result<(), YamlError> {
+    if env.is_const_in_scope(var) {
+        return Err(YamlError::EvalError(EvalError::AssignToConst(
+            env.name(var),
+        )));
+    }
+
+    if env.bind(var, value, constant) {
+        Ok(())
+    } else {
+        Err(YamlError::EvalError(EvalError::VarNotInEnv(env.name(var))))
+    }
+}
+
+/// Sets an existing variable in the environment to a value
+/// returns an error if the variable is read-only or if it is not declared
+/// and the environment is not lenient
+pub(crate) fn assign_var<K: Copy>(
+    env: &mut (impl Bindings<K> + ?Sized),
+    var: K,
+    value: Lit,
+) -> Result<(), YamlError> {
+    if env.is_const(var) {
+        return Err(YamlError::EvalError(EvalError::AssignToConst(
+            env.name(var),
+        )));
+    }
+
+    if !env.assign(var, value) && !env.is_lenient() {
+        return Err(YamlError::EvalError(EvalError::AssignToUndeclared(
+            env.name(var),
+        )));
+    }
+    Ok(())
+}
+
+/// Binds a variable name to a value in the current scope of the environment
+/// (see declare_var)
+pub fn declare(
+    env: &mut impl Environment,
+    name: &str,
+    value: Lit,
+    constant: bool,
+) -> Result<(), YamlError> {
+    declare_var(env, name, value, constant)
+}
+
+/// Sets an existing variable name in the environment to a value (see assign_var)
+pub fn assign(env: &mut impl Environment, name: &str, value: Lit) -> Result<(), YamlError> {
+    assign_var(env, name, value)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ast::{Exp, Lit, Op};
     use crate::environment::{ASTEnvironment, Environment};
+    use crate::errors::{EvalError, YamlError};
 
     #[test]
     fn test_arith_ast() {
@@ -252,6 +334,87 @@ mod tests {
         assert_eq!(env.get("x"), Some(Lit::Number(3)));
     }
 
+    #[test]
+    fn test_assign_undeclared() {
+        // Test that assigning to a variable that was never declared is an error
+        // unless the environment is lenient, in which case nothing is bound
+
+        let assign_tree = Exp::Assign("x".to_owned(), Box::new(Exp::Lit(Lit::Number(1))));
+
+        let mut env = ASTEnvironment::new();
+        assert_eq!(
+            assign_tree.eval(&mut env),
+            Err(YamlError::EvalError(EvalError::AssignToUndeclared(
+                "x".to_owned()
+            )))
+        );
+
+        let mut env = ASTEnvironment::lenient();
+        assert_eq!(assign_tree.eval(&mut env), Ok(Exp::Lit(Lit::Number(1))));
+        assert_eq!(env.get("x"), None);
+    }
+
+    #[test]
+    fn test_assign_const() {
+        // Test that a read-only variable cannot be assigned to or redeclared,
+        // whether it was bound by the host or by a const declaration
+
+        let mut env = ASTEnvironment::new();
+        env.set_const("season", Lit::Str("spring".to_owned()));
+
+        let assign_tree = Exp::Assign(
+            "season".to_owned(),
+            Box::new(Exp::Lit(Lit::Str("winter".to_owned()))),
+        );
+        assert_eq!(
+            assign_tree.eval(&mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "season".to_owned()
+            )))
+        );
+
+        let declare_tree = Exp::DeclareConst("x".to_owned(), Box::new(Exp::Lit(Lit::Number(1))));
+        assert_eq!(declare_tree.eval(&mut env), Ok(Exp::Lit(Lit::Number(1))));
+        assert!(env.is_const("x"));
+
+        let declare_tree = Exp::Declare("x".to_owned(), Box::new(Exp::Lit(Lit::Number(2))));
+        assert_eq!(
+            declare_tree.eval(&mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "x".to_owned()
+            )))
+        );
+        assert_eq!(env.get("x"), Some(Lit::Number(1)));
+        assert_eq!(env.get("season"), Some(Lit::Str("spring".to_owned())));
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let mut env = ASTEnvironment::new();
+        env.set("a", Lit::Number(0));
+
+        let ast = Exp::BinaryOp(
+            Op::Divide,
+            Box::new(Exp::Lit(Lit::Number(5))),
+            Box::new(Exp::Variable("a".to_owned())),
+        );
+        assert_eq!(
+            ast.eval(&mut env),
+            Err(YamlError::EvalError(EvalError::DivisionByZero))
+        );
+
+        // dividing a real by zero follows floating point rules like it did before
+        let ast = Exp::BinaryOp(
+            Op::Divide,
+            Box::new(Exp::Lit(Lit::Decimal(5.0))),
+            Box::new(Exp::Variable("a".to_owned())),
+        );
+        assert_eq!(
+            ast.eval(&mut env),
+            Ok(Exp::Lit(Lit::Decimal(f64::INFINITY)))
+        );
+    }
+
     #[test]
     fn test_equality() {
         let mut env = ASTEnvironment::new();
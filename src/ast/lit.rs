@@ -2,6 +2,7 @@ use self::Lit::*;
 use crate::ast::Op;
 use crate::errors::{EvalError, YamlError};
 use std::ops::{Add, Div, Mul, Not, Rem, Sub};
+use yaml_rust::yaml::Yaml;
 
 #[derive(Debug, PartialEq, Clone, PartialOrd)]
 pub enum Lit {
@@ -12,7 +13,103 @@ pub enum Lit {
     Nil,
 }
 
+/// The type of a literal value
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum LitType {
+    Number,
+    Bool,
+    Decimal,
+    Str,
+    Nil,
+}
+
+impl LitType {
+    /// Parses a type name used in schema blocks ("integer", "decimal", "string", etc.)
+    pub fn from_name(name: &str) -> Option<LitType> {
+        match name {
+            "integer" | "number" => Some(LitType::Number),
+            "boolean" | "bool" => Some(LitType::Bool),
+            "decimal" | "real" => Some(LitType::Decimal),
+            "string" => Some(LitType::Str),
+            "nil" | "null" => Some(LitType::Nil),
+            _ => None,
+        }
+    }
+
+    /// Returns a value of the type that can stand in for
+    /// any value of the type when checking operators
+    pub fn sample(self) -> Lit {
+        match self {
+            LitType::Number => Number(1),
+            LitType::Bool => Bool(true),
+            LitType::Decimal => Decimal(1.0),
+            LitType::Str => Str(String::new()),
+            LitType::Nil => Nil,
+        }
+    }
+}
+
 impl Lit {
+    /// Returns the type of the literal
+    pub fn lit_type(&self) -> LitType {
+        match *self {
+            Number(_) => LitType::Number,
+            Bool(_) => LitType::Bool,
+            Decimal(_) => LitType::Decimal,
+            Str(_) => LitType::Str,
+            Nil => LitType::Nil,
+        }
+    }
+
+    /// Converts a scalar YAML value to a literal
+    /// returns None for arrays, hashes and integers that do not fit in a Number
+    pub fn from_yaml(yaml: &Yaml) -> Option<Lit> {
+        match *yaml {
+            Yaml::Integer(i) => i32::try_from(i).ok().map(Number),
+            Yaml::Real(_) => yaml.as_f64().map(Decimal),
+            Yaml::String(ref s) => Some(Str(s.clone())),
+            Yaml::Boolean(b) => Some(Bool(b)),
+            Yaml::Null => Some(Nil),
+            _ => None,
+        }
+    }
+
+    /// Applies a unary operator to the literal
+    pub fn apply_unary(self, op: Op) -> Result<Lit, YamlError> {
+        match op {
+            Op::Not => !self,
+            // Non-unary operators (for exhaustiveness checking)
+            Op::Plus
+            | Op::Minus
+            | Op::Times
+            | Op::Divide
+            | Op::Modulo
+            | Op::Exponent
+            | Op::And
+            | Op::Or
+            | Op::Equal
+            | Op::NotEqual => Err(YamlError::EvalError(EvalError::NotUnOp(op))),
+        }
+    }
+
+    /// Applies a binary operator with the literal as the left operand
+    pub fn apply_binary(self, op: Op, other: Lit) -> Result<Lit, YamlError> {
+        match op {
+            Op::Plus => self + other,
+            Op::Minus => self - other,
+            Op::Times => self * other,
+            Op::Divide => self / other,
+            Op::Modulo => self % other,
+            Op::Exponent => self.exp(other),
+            Op::And => self.and(other),
+            Op::Or => self.or(other),
+            Op::Equal => Ok(Lit::Bool(self == other)),
+            Op::NotEqual => Ok(Lit::Bool(self != other)),
+            // Non-binary operators (for exhaustiveness checking)
+            Op::Not => Err(YamlError::EvalError(EvalError::NotBinOp(op))),
+        }
+    }
+
     pub fn and(&self, other: Lit) -> Result<Lit, YamlError> {
         match (self, other) {
             (&Bool(b1), Bool(b2)) => Ok(Lit::Bool(b1 && b2)),
@@ -106,6 +203,7 @@ impl Div<Lit> for Lit {
 
     fn div(self, other: Lit) -> Result<Lit, YamlError> {
         match (self, other) {
+            (Number(_), Number(0)) => Err(YamlError::EvalError(EvalError::DivisionByZero)),
             (Number(n1), Number(n2)) => Ok(Lit::Number(n1 / n2)),
             (Number(n), Decimal(d)) => Ok(Lit::Decimal((n as f64) / d)),
             (Decimal(d), Number(n)) => Ok(Lit::Decimal(d / (n as f64))),
@@ -123,6 +221,7 @@ impl Rem<Lit> for Lit {
 
     fn rem(self, other: Lit) -> Result<Lit, YamlError> {
         match (self, other) {
+            (Number(_), Number(0)) => Err(YamlError::EvalError(EvalError::DivisionByZero)),
             (Number(n1), Number(n2)) => Ok(Lit::Number(n1 % n2)),
             (Number(n), Decimal(d)) => Ok(Lit::Decimal((n as f64) % d)),
             (Decimal(d), Number(n)) => Ok(Lit::Decimal(d % (n as f64))),
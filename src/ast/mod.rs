@@ -17,4 +17,4 @@ pub enum Op {
 }
 
 pub use crate::ast::exp::Exp;
-pub use crate::ast::lit::Lit;
+pub use crate::ast::lit::{Lit, LitType};
@@ -0,0 +1,653 @@
+use crate::ast::{Exp, Lit, LitType, Op};
+use crate::environment::ASTEnvironment;
+use crate::errors::YamlError;
+use crate::parser::compile;
+use std::collections::HashMap;
+use yaml_rust::yaml::Yaml;
+
+/// The kind of problem found by the checker
+#[derive(Debug, PartialEq)]
+pub enum CheckErrorKind {
+    /// When the expression cannot be lexed or parsed
+    Syntax(YamlError),
+    /// When the type to apply a unary operator is invalid
+    /// (!"hello")
+    InvalidUnOp(Op, LitType),
+    /// When the types to apply a binary operator are invalid
+    /// ("spring" - 1)
+    InvalidBinOp(Op, LitType, LitType),
+    /// When a variable is used or assigned without being declared
+    UndeclaredVariable(String),
+    /// When the condition of an if or while is not a boolean
+    ConditionNotBool(LitType),
+    /// When a do or else branch can never be executed because
+    /// the condition is constant
+    UnreachableBranch(String),
+    /// When a statement comes after a return
+    UnreachableStatement,
+    /// When a type in a schema block is not a known type name
+    UnknownSchemaType(String),
+    /// When an integer is divided by a divisor that folds to zero
+    /// (5 / 0, a % (2 - 2))
+    DivisionByZero,
+}
+
+/// A problem found by the checker along with the
+/// path in the document where it was found (cricket.wing_color[0])
+#[derive(Debug, PartialEq)]
+pub struct CheckError {
+    pub path: String,
+    pub kind: CheckErrorKind,
+}
+
+impl CheckError {
+    pub fn description(&self) -> String {
+        let message = match self.kind {
+            CheckErrorKind::Syntax(ref err) => err.description(),
+            CheckErrorKind::InvalidUnOp(op, ty) => {
+                format!("Invalid type ({:?}) applied to operator {:?}", ty, op)
+            }
+            CheckErrorKind::InvalidBinOp(op, ty1, ty2) => {
+                format!(
+                    "Invalid types ({:?}, {:?}) applied to operator {:?}",
+                    ty1, ty2, op
+                )
+            }
+            CheckErrorKind::UndeclaredVariable(ref name) => {
+                format!("Variable {:?} is not declared", name)
+            }
+            CheckErrorKind::ConditionNotBool(ty) => {
+                format!("Condition has type {:?} instead of Bool", ty)
+            }
+            CheckErrorKind::UnreachableBranch(ref branch) => {
+                format!("Branch {:?} can never be executed", branch)
+            }
+            CheckErrorKind::UnreachableStatement => "Statement after return".to_owned(),
+            CheckErrorKind::UnknownSchemaType(ref name) => {
+                format!("Unknown type {:?} in schema", name)
+            }
+            CheckErrorKind::DivisionByZero => "Division by zero".to_owned(),
+        };
+
+        format!("{}: {}", self.path, message)
+    }
+}
+
+/// The declared variables of a scope with their types
+/// (None if the type cannot be known before evaluating)
+type Scope = HashMap<String, Option<LitType>>;
+
+/// Walks YAML documents and reports problems in
+/// their expressions without evaluating them
+pub struct Checker {
+    /// The declared variables in each scope with their types
+    scopes: Vec<Scope>,
+    errors: Vec<CheckError>,
+}
+
+impl Checker {
+    pub fn new(inputs: &HashMap<String, LitType>) -> Checker {
+        let globals = inputs
+            .iter()
+            .map(|(name, ty)| (name.clone(), Some(*ty)))
+            .collect();
+
+        Checker {
+            scopes: vec![globals],
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns the problems found so far
+    pub fn into_errors(self) -> Vec<CheckError> {
+        self.errors
+    }
+
+    fn error(&mut self, path: &str, kind: CheckErrorKind) {
+        self.errors.push(CheckError {
+            path: path.to_owned(),
+            kind,
+        });
+    }
+
+    fn lookup(&self, name: &str) -> Option<Option<LitType>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn declare(&mut self, name: &str, ty: Option<LitType>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), ty);
+        }
+    }
+
+    fn assign(&mut self, name: &str, ty: Option<LitType>) -> bool {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.get_mut(name) {
+                *binding = ty;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Infers the type of an expression, reporting any problems
+    /// returns None if the type cannot be known before evaluating
+    pub fn check_exp(&mut self, exp: &Exp, path: &str) -> Option<LitType> {
+        match *exp {
+            Exp::Lit(ref lit) => Some(lit.lit_type()),
+            Exp::Variable(ref name) => match self.lookup(name) {
+                Some(ty) => ty,
+                None => {
+                    self.error(path, CheckErrorKind::UndeclaredVariable(name.clone()));
+                    None
+                }
+            },
+            Exp::Declare(ref name, ref exp) | Exp::DeclareConst(ref name, ref exp) => {
+                let ty = self.check_exp(exp, path);
+                self.declare(name, ty);
+                ty
+            }
+            Exp::Assign(ref name, ref exp) => {
+                let ty = self.check_exp(exp, path);
+                if !self.assign(name, ty) {
+                    self.error(path, CheckErrorKind::UndeclaredVariable(name.clone()));
+                }
+                ty
+            }
+            Exp::UnaryOp(op, ref exp) => {
+                let ty = self.check_exp(exp, path)?;
+                // applying the operator to a stand-in value keeps the
+                // checker's typing rules the same as the evaluator's
+                match ty.sample().apply_unary(op) {
+                    Ok(lit) => Some(lit.lit_type()),
+                    Err(_) => {
+                        self.error(path, CheckErrorKind::InvalidUnOp(op, ty));
+                        None
+                    }
+                }
+            }
+            Exp::BinaryOp(op, ref exp1, ref exp2) => {
+                let ty1 = self.check_exp(exp1, path);
+                let ty2 = self.check_exp(exp2, path);
+                // the optimizer leaves divisions by zero unfolded
+                // so they only fail if they are evaluated
+                if matches!(op, Op::Divide | Op::Modulo)
+                    && matches!(**exp2, Exp::Lit(Lit::Number(0)))
+                {
+                    self.error(path, CheckErrorKind::DivisionByZero);
+                }
+                let (ty1, ty2) = (ty1?, ty2?);
+                match ty1.sample().apply_binary(op, ty2.sample()) {
+                    Ok(lit) => Some(lit.lit_type()),
+                    Err(_) => {
+                        self.error(path, CheckErrorKind::InvalidBinOp(op, ty1, ty2));
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks an expression string (with the "~>" prefix)
+    /// returns the expression if it was parsed and its type
+    fn check_str(&mut self, s: &str, path: &str) -> (Option<Exp>, Option<LitType>) {
+        let split_vec = s.split("~>").collect::<Vec<_>>();
+        match compile(split_vec[1]) {
+            Ok(exp) => {
+                let ty = self.check_exp(&exp, path);
+                (Some(exp), ty)
+            }
+            Err(err) => {
+                self.error(path, CheckErrorKind::Syntax(err));
+                (None, None)
+            }
+        }
+    }
+
+    /// Checks a YAML fragment that will be evaluated
+    /// returns true if the fragment always returns
+    pub fn check(&mut self, yaml: &Yaml, path: &str) -> bool {
+        match *yaml {
+            Yaml::String(ref s) if s.contains("~>") => {
+                self.check_str(s, path);
+                false
+            }
+            Yaml::Array(ref arr) => {
+                let mut returned = false;
+                for (i, v) in arr.iter().enumerate() {
+                    let path = format!("{}[{}]", path, i);
+                    if returned {
+                        self.error(&path, CheckErrorKind::UnreachableStatement);
+                        break;
+                    }
+                    returned = self.check(v, &path);
+                }
+                returned
+            }
+            Yaml::Hash(ref h) => {
+                if let Some((Yaml::String(keyword), v)) = h.iter().next() {
+                    let path = format!("{}.{}", path, keyword);
+                    match keyword.as_str() {
+                        "if" | "while" => self.check_conditional(keyword, v, &path),
+                        "return" => {
+                            self.check(v, &path);
+                            return true;
+                        }
+                        _ => {}
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    // checks the conditions and the nested do and else blocks of an if or while
+    fn check_conditional(&mut self, keyword: &str, v: &Yaml, path: &str) {
+        let arr = match *v {
+            Yaml::Array(ref arr) => arr,
+            _ => return,
+        };
+
+        // the constant value of the anded conditions (None if not constant)
+        let mut constant = Some(true);
+        for (i, val) in arr.iter().enumerate() {
+            let path = format!("{}[{}]", path, i);
+            match *val {
+                Yaml::String(ref s) if s.contains("~>") => {
+                    let (exp, ty) = self.check_str(s, &path);
+                    match ty {
+                        Some(LitType::Bool) | None => {}
+                        Some(ty) => self.error(&path, CheckErrorKind::ConditionNotBool(ty)),
+                    }
+
+                    let value = exp.as_ref().and_then(constant_value);
+                    constant = match (constant, value) {
+                        (Some(false), _) | (_, Some(false)) => Some(false),
+                        (Some(true), Some(true)) => Some(true),
+                        _ => None,
+                    };
+                }
+                Yaml::Hash(ref h) => {
+                    // the variable types after each branch that can run
+                    let before = self.scopes.clone();
+                    let mut outcomes = Vec::new();
+                    let mut has_else = false;
+
+                    for (key, body) in h {
+                        if let Yaml::String(ref branch) = *key {
+                            let unreachable = match (keyword, branch.as_str()) {
+                                (_, "do") => constant == Some(false),
+                                ("if", "else") => constant == Some(true),
+                                _ => continue,
+                            };
+                            has_else |= branch == "else";
+
+                            let path = format!("{}.{}", path, branch);
+                            if unreachable {
+                                self.error(
+                                    &path,
+                                    CheckErrorKind::UnreachableBranch(branch.clone()),
+                                );
+                            }
+
+                            self.scopes.push(HashMap::new());
+                            self.check(body, &path);
+                            self.scopes.pop();
+
+                            let after = std::mem::replace(&mut self.scopes, before.clone());
+                            if !unreachable {
+                                outcomes.push(after);
+                            }
+                        }
+                    }
+
+                    // a while body or an if without an else may not run at all
+                    let skippable = keyword != "if" || (!has_else && constant != Some(true));
+                    if skippable || outcomes.is_empty() {
+                        outcomes.push(before);
+                    }
+                    self.scopes = merge_scopes(outcomes);
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// merges the variable types after each way a conditional can go,
+// a variable keeps its type only if it has the same type after all of them
+fn merge_scopes(mut outcomes: Vec<Vec<Scope>>) -> Vec<Scope> {
+    let mut merged = outcomes.pop().unwrap_or_default();
+    for outcome in outcomes {
+        for (scope, other) in merged.iter_mut().zip(outcome) {
+            for (name, ty) in scope.iter_mut() {
+                if other.get(name) != Some(ty) {
+                    *ty = None;
+                }
+            }
+        }
+    }
+    merged
+}
+
+// returns the value of a condition if it does not depend on any variables
+fn constant_value(exp: &Exp) -> Option<bool> {
+    fn is_constant(exp: &Exp) -> bool {
+        match *exp {
+            Exp::Lit(_) => true,
+            Exp::UnaryOp(_, ref exp) => is_constant(exp),
+            Exp::BinaryOp(_, ref exp1, ref exp2) => is_constant(exp1) && is_constant(exp2),
+            Exp::Variable(_) | Exp::Declare(..) | Exp::DeclareConst(..) | Exp::Assign(..) => false,
+        }
+    }
+
+    if !is_constant(exp) {
+        return None;
+    }
+
+    match exp.eval(&mut ASTEnvironment::new()) {
+        Ok(Exp::Lit(Lit::Bool(b))) => Some(b),
+        _ => None,
+    }
+}
+
+/// Reads the variable types declared in a schema block
+/// (a mapping from variable name to type name)
+fn read_schema(schema: &Yaml, inputs: &mut HashMap<String, LitType>, errors: &mut Vec<CheckError>) {
+    if let Yaml::Hash(ref h) = *schema {
+        for (key, val) in h {
+            if let (Yaml::String(name), Yaml::String(ty)) = (key, val) {
+                match LitType::from_name(ty) {
+                    Some(ty) => {
+                        inputs.insert(name.clone(), ty);
+                    }
+                    None => errors.push(CheckError {
+                        path: format!("schema.{}", name),
+                        kind: CheckErrorKind::UnknownSchemaType(ty.clone()),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+// checks every evaluable value in a YAML document, treating each
+// value in a hash that is not a keyword as a separate entry point
+fn check_entries(yaml: &Yaml, path: &str, inputs: &HashMap<String, LitType>) -> Vec<CheckError> {
+    if let Yaml::Hash(ref h) = *yaml {
+        let is_keyword = matches!(
+            h.iter().next(),
+            Some((Yaml::String(k), _)) if matches!(k.as_str(), "if" | "while" | "return")
+        );
+
+        if !is_keyword {
+            let mut errors = Vec::new();
+            for (key, val) in h {
+                let path = match *key {
+                    Yaml::String(ref s) if path.is_empty() => s.clone(),
+                    Yaml::String(ref s) => format!("{}.{}", path, s),
+                    _ => continue,
+                };
+                errors.extend(check_entries(val, &path, inputs));
+            }
+            return errors;
+        }
+    }
+
+    check(yaml, path, inputs)
+}
+
+/// Checks a single YAML fragment the way it would be evaluated with
+/// the given input variable types
+pub fn check(yaml: &Yaml, path: &str, inputs: &HashMap<String, LitType>) -> Vec<CheckError> {
+    let mut checker = Checker::new(inputs);
+    checker.check(yaml, path);
+    checker.into_errors()
+}
+
+/// Checks every rule in a document given the input variable types from the
+/// host merged with the types declared in the document's top level schema block:
+///
+/// ```yaml
+/// schema:
+///   current_season: string
+///   another_beetle_nearby: integer
+/// ```
+pub fn check_document(doc: &Yaml, inputs: &HashMap<String, LitType>) -> Vec<CheckError> {
+    let mut inputs = inputs.clone();
+    let mut errors = Vec::new();
+
+    if let Yaml::Hash(ref h) = *doc {
+        if let Some(schema) = h.get(&Yaml::String("schema".to_owned())) {
+            read_schema(schema, &mut inputs, &mut errors);
+        }
+
+        for (key, val) in h {
+            if let Yaml::String(ref s) = *key
+                && s != "schema"
+            {
+                errors.extend(check_entries(val, s, &inputs));
+            }
+        }
+    } else {
+        errors.extend(check(doc, "", &inputs));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn check_str(s: &str) -> Vec<CheckError> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        check_document(&docs[0], &HashMap::new())
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let s = "
+        schema:
+          current_season: string
+        foo:
+          - '~> current_season - 1'
+        ";
+        assert_eq!(
+            check_str(s),
+            vec![CheckError {
+                path: "foo[0]".to_owned(),
+                kind: CheckErrorKind::InvalidBinOp(Op::Minus, LitType::Str, LitType::Number),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_declared_types_flow() {
+        // Test that declared variables carry their types and
+        // local variables are not visible after their block
+        let s = "
+        foo:
+          - '~> a := 2.5'
+          - if:
+            - '~> a == 2'
+            - do:
+              - '~> c := \"hello\"'
+              - '~> a = c'
+              else:
+              - '~> a = \"bye\"'
+          - '~> !a'
+          - '~> c'
+        ";
+        assert_eq!(
+            check_str(s),
+            vec![
+                CheckError {
+                    path: "foo[2]".to_owned(),
+                    kind: CheckErrorKind::InvalidUnOp(Op::Not, LitType::Str),
+                },
+                CheckError {
+                    path: "foo[3]".to_owned(),
+                    kind: CheckErrorKind::UndeclaredVariable("c".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branch_types_merge() {
+        // Test that a variable assigned in a branch that may not run
+        // has an unknown type after the conditional
+        let s = "
+        foo:
+          - '~> a := 1'
+          - if:
+            - '~> a == 2'
+            - do:
+              - '~> a = \"two\"'
+          - '~> a - 1'
+          - while:
+            - '~> a == 1'
+            - do:
+              - '~> a = \"one\"'
+          - '~> a - 1'
+        bar:
+          - '~> a := 1'
+          - if:
+            - '~> 1 == 1'
+            - do:
+              - '~> a = \"one\"'
+          - '~> a - 1'
+        ";
+        assert_eq!(
+            check_str(s),
+            vec![CheckError {
+                path: "bar[2]".to_owned(),
+                kind: CheckErrorKind::InvalidBinOp(Op::Minus, LitType::Str, LitType::Number),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_undeclared_variables() {
+        let mut inputs = HashMap::new();
+        inputs.insert("another_beetle_nearby".to_owned(), LitType::Number);
+
+        let s = "
+        beetle:
+          wing_color:
+            - if:
+              - '~> another_beetle_nearby == 1'
+              - do:
+                - '~> another_beetle_neaby = 0'
+                - 'blue'
+                else:
+                - 'red'
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert_eq!(
+            check_document(&docs[0], &inputs),
+            vec![CheckError {
+                path: "beetle.wing_color[0].if[1].do[0]".to_owned(),
+                kind: CheckErrorKind::UndeclaredVariable("another_beetle_neaby".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let s = "
+        foo:
+          - if:
+            - '~> 1 == 1'
+            - do:
+              - 'red'
+              else:
+              - 'blue'
+          - while:
+            - '~> 1 != 1'
+            - do:
+              - 'green'
+          - return: 'red'
+          - 'blue'
+        ";
+        assert_eq!(
+            check_str(s),
+            vec![
+                CheckError {
+                    path: "foo[0].if[1].else".to_owned(),
+                    kind: CheckErrorKind::UnreachableBranch("else".to_owned()),
+                },
+                CheckError {
+                    path: "foo[1].while[1].do".to_owned(),
+                    kind: CheckErrorKind::UnreachableBranch("do".to_owned()),
+                },
+                CheckError {
+                    path: "foo[3]".to_owned(),
+                    kind: CheckErrorKind::UnreachableStatement,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        // Test that a divisor folded to zero is reported without
+        // evaluating the branch it is in
+        let s = "
+        schema:
+          legs: integer
+        foo:
+          - '~> legs / (2 - 2)'
+          - '~> legs % 0'
+          - '~> legs / 2.0'
+        ";
+        assert_eq!(
+            check_str(s),
+            vec![
+                CheckError {
+                    path: "foo[0]".to_owned(),
+                    kind: CheckErrorKind::DivisionByZero,
+                },
+                CheckError {
+                    path: "foo[1]".to_owned(),
+                    kind: CheckErrorKind::DivisionByZero,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_condition_and_schema_errors() {
+        let s = "
+        schema:
+          a: integer
+          b: color
+        foo:
+          - if:
+            - '~> a + 1'
+            - do:
+              - 'red'
+        ";
+        assert_eq!(
+            check_str(s),
+            vec![
+                CheckError {
+                    path: "schema.b".to_owned(),
+                    kind: CheckErrorKind::UnknownSchemaType("color".to_owned()),
+                },
+                CheckError {
+                    path: "foo[0].if[0]".to_owned(),
+                    kind: CheckErrorKind::ConditionNotBool(LitType::Number),
+                },
+            ]
+        );
+    }
+}
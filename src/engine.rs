@@ -0,0 +1,389 @@
+use crate::ast::Lit;
+use crate::environment::{ASTEnvironment, Environment};
+#[cfg(feature = "serde")]
+use crate::errors::SerdeError;
+use crate::errors::{EvalError, LoadError, PathError, YamlError};
+use crate::path::get_path;
+use crate::vm::Vm;
+use crate::yaml::{ExpRunner, TreeWalker, evaluate_with};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use yaml_rust::YamlLoader;
+use yaml_rust::yaml::Yaml;
+
+/// A function the host registers with an engine
+///
+/// It gets the values of its arguments and returns a value
+/// or a description of why it failed
+pub type Function = dyn Fn(&[Lit]) -> Result<Lit, String> + Send + Sync;
+
+/// Limits on the documents an engine loads and evaluates,
+/// None means there is no limit (the default)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    /// The most times a single while loop can run
+    pub max_iterations: Option<usize>,
+    /// The longest expression (in bytes) that can be run
+    pub max_expression_len: Option<usize>,
+    /// The deepest a document can be nested (a scalar has a depth of 0)
+    pub max_depth: Option<usize>,
+}
+
+/// Loads documents and evaluates them with the same configuration,
+/// limits and functions
+///
+/// A function is called like a keyword: a hash with the name of the function as
+/// its key calls it with the values of the items of the list (or the single value)
+/// after the key.
+///
+/// ```yaml
+/// wing_span:
+///   max: ['~> span', 2.5]
+/// ```
+#[derive(Clone, Default)]
+pub struct Engine {
+    limits: Limits,
+    lenient: bool,
+    bytecode: bool,
+    functions: HashMap<String, Arc<Function>>,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Turns lenient assignment on or off for the environments
+    /// created by new_environment
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Evaluates documents by compiling their expressions to bytecode
+    /// instead of walking the AST (faster for loops)
+    pub fn set_bytecode(&mut self, bytecode: bool) {
+        self.bytecode = bytecode;
+    }
+
+    /// Registers a function that documents can call,
+    /// replacing any function with the same name
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        function: impl Fn(&[Lit]) -> Result<Lit, String> + Send + Sync + 'static,
+    ) {
+        self.functions.insert(name.to_owned(), Arc::new(function));
+    }
+
+    /// Creates an empty environment with the configuration of the engine
+    pub fn new_environment(&self) -> ASTEnvironment {
+        let mut env = ASTEnvironment::new();
+        env.set_lenient(self.lenient);
+        env
+    }
+
+    /// Parses the first document of a string (an empty string is a null document)
+    pub fn load_str(&self, s: &str) -> Result<Document<'_>, LoadError> {
+        let docs = YamlLoader::load_from_str(s).map_err(|e| LoadError::Syntax(e.to_string()))?;
+        let yaml = docs.into_iter().next().unwrap_or(Yaml::Null);
+
+        if let Some(limit) = self.limits.max_depth
+            && depth(&yaml) > limit
+        {
+            return Err(LoadError::TooDeep(limit));
+        }
+
+        Ok(Document { engine: self, yaml })
+    }
+
+    /// Reads and parses the first document of a file
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<Document<'_>, LoadError> {
+        let s = fs::read_to_string(path).map_err(|e| LoadError::Io(e.to_string()))?;
+        self.load_str(&s)
+    }
+}
+
+fn depth(yaml: &Yaml) -> usize {
+    match *yaml {
+        Yaml::Array(ref arr) => 1 + arr.iter().map(depth).max().unwrap_or(0),
+        Yaml::Hash(ref h) => 1 + h.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+// runs expressions with a runner applying the limits and functions of an engine
+struct EngineRunner<'e, R> {
+    engine: &'e Engine,
+    runner: R,
+}
+
+impl<R: ExpRunner> ExpRunner for EngineRunner<'_, R> {
+    fn run(&mut self, s: &str, env: &mut impl Environment) -> Result<Lit, YamlError> {
+        if let Some(limit) = self.engine.limits.max_expression_len
+            && s.len() > limit
+        {
+            return Err(YamlError::EvalError(EvalError::ExpressionTooLong(limit)));
+        }
+        self.runner.run(s, env)
+    }
+
+    fn is_function(&self, name: &str) -> bool {
+        self.engine.functions.contains_key(name)
+    }
+
+    fn call(&mut self, name: &str, args: &[Lit]) -> Result<Lit, YamlError> {
+        match self.engine.functions.get(name) {
+            Some(function) => function(args).map_err(|err| {
+                YamlError::EvalError(EvalError::FunctionFailed(name.to_owned(), err))
+            }),
+            None => Err(YamlError::EvalError(EvalError::FunctionNotFound(
+                name.to_owned(),
+            ))),
+        }
+    }
+
+    fn max_iterations(&self) -> Option<usize> {
+        self.engine.limits.max_iterations
+    }
+}
+
+/// A document loaded by an engine
+pub struct Document<'e> {
+    engine: &'e Engine,
+    yaml: Yaml,
+}
+
+impl Document<'_> {
+    pub fn yaml(&self) -> &Yaml {
+        &self.yaml
+    }
+
+    /// Returns the value at a path ("cricket.wing_color", "bugs[3].name")
+    /// without evaluating it, fails if the path is not valid
+    pub fn get(&self, path: &str) -> Result<Option<&Yaml>, PathError> {
+        get_path(&self.yaml, path)
+    }
+
+    /// Evaluates the whole document
+    pub fn eval(&self, env: &mut impl Environment) -> Result<Value, YamlError> {
+        self.eval_yaml(&self.yaml, env)
+    }
+
+    /// Evaluates the value at a path ("cricket.wing_color", "bugs[3].name")
+    pub fn eval_path(&self, path: &str, env: &mut impl Environment) -> Result<Value, YamlError> {
+        match self.get(path).map_err(YamlError::PathError)? {
+            Some(yaml) => self.eval_yaml(yaml, env),
+            None => Err(YamlError::EvalError(EvalError::PathNotFound(
+                path.to_owned(),
+            ))),
+        }
+    }
+
+    fn eval_yaml(&self, yaml: &Yaml, env: &mut impl Environment) -> Result<Value, YamlError> {
+        let result = if self.engine.bytecode {
+            let mut runner = EngineRunner {
+                engine: self.engine,
+                runner: Vm::new(),
+            };
+            evaluate_with(yaml, env, &mut runner)
+        } else {
+            let mut runner = EngineRunner {
+                engine: self.engine,
+                runner: TreeWalker,
+            };
+            evaluate_with(yaml, env, &mut runner)
+        };
+        result.map(Value)
+    }
+}
+
+/// The result of evaluating a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value(Yaml);
+
+impl Value {
+    pub fn as_i64(&self) -> Option<i64> {
+        self.0.as_i64()
+    }
+
+    /// Returns reals and integers as a float
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.0 {
+            Yaml::Integer(i) => Some(i as f64),
+            ref yaml => yaml.as_f64(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        self.0.as_str()
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        self.0.as_bool()
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.0.is_null()
+    }
+
+    /// Converts a scalar value to a literal (to store it in an environment)
+    pub fn to_lit(&self) -> Option<Lit> {
+        Lit::from_yaml(&self.0)
+    }
+
+    pub fn as_yaml(&self) -> &Yaml {
+        &self.0
+    }
+
+    pub fn into_yaml(self) -> Yaml {
+        self.0
+    }
+
+    /// Converts the value to a Rust value (a struct for a hash, a Vec for an array)
+    #[cfg(feature = "serde")]
+    pub fn deserialize<'de, T: serde::Deserialize<'de>>(&'de self) -> Result<T, SerdeError> {
+        crate::serde::from_yaml(&self.0)
+    }
+}
+
+impl From<Value> for Yaml {
+    fn from(value: Value) -> Yaml {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUGS: &str = "
+    cricket:
+      wing_color:
+        - if:
+          - '~> current_season == \"spring\"'
+          - do:
+            - 'red'
+            else:
+            - 'blue'
+    beetle:
+      legs: '~> pairs * 2'
+      wing_span:
+        max: ['~> span', 2.5]
+    ";
+
+    fn max(args: &[Lit]) -> Result<Lit, String> {
+        let mut max = None;
+        for arg in args {
+            let value = match *arg {
+                Lit::Number(n) => n as f64,
+                Lit::Decimal(d) => d,
+                ref lit => return Err(format!("{:?} is not a number", lit)),
+            };
+            max = Some(max.map_or(value, |max: f64| max.max(value)));
+        }
+        max.map(Lit::Decimal)
+            .ok_or_else(|| "no arguments".to_owned())
+    }
+
+    #[test]
+    fn test_eval_path() {
+        let mut engine = Engine::new();
+        engine.register_function("max", max);
+
+        for bytecode in [false, true] {
+            engine.set_bytecode(bytecode);
+            let doc = engine.load_str(BUGS).unwrap();
+            let mut env = engine.new_environment();
+            env.set("current_season", Lit::Str("spring".to_owned()));
+            env.set("pairs", Lit::Number(3));
+            env.set("span", Lit::Number(3));
+
+            let color = doc.eval_path("cricket.wing_color", &mut env).unwrap();
+            assert_eq!(color.as_str(), Some("red"));
+            assert_eq!(
+                doc.eval_path("beetle.legs", &mut env).unwrap().as_i64(),
+                Some(6)
+            );
+            let span = doc.eval_path("beetle.wing_span", &mut env).unwrap();
+            assert_eq!(span.as_f64(), Some(3.0));
+
+            env.set("span", Lit::Str("wide".to_owned()));
+            assert_eq!(
+                doc.eval_path("beetle.wing_span", &mut env),
+                Err(YamlError::EvalError(EvalError::FunctionFailed(
+                    "max".to_owned(),
+                    "Str(\"wide\") is not a number".to_owned()
+                )))
+            );
+            assert_eq!(
+                doc.eval_path("ant", &mut env),
+                Err(YamlError::EvalError(EvalError::PathNotFound(
+                    "ant".to_owned()
+                )))
+            );
+            assert_eq!(
+                doc.eval_path("beetle[legs]", &mut env),
+                Err(YamlError::PathError(PathError::InvalidIndex(
+                    "legs".to_owned()
+                )))
+            );
+            assert_eq!(doc.get("beetle."), Err(PathError::EmptyKey));
+        }
+
+        // without the function the hash is a plain value
+        let engine = Engine::new();
+        let doc = engine.load_str(BUGS).unwrap();
+        let span = doc
+            .eval_path("beetle.wing_span", &mut ASTEnvironment::new())
+            .unwrap();
+        assert_eq!(span.as_f64(), None);
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut engine = Engine::new();
+        engine.set_limits(Limits {
+            max_iterations: Some(10),
+            max_expression_len: Some(20),
+            max_depth: Some(6),
+        });
+
+        let s = "
+        forever:
+          - while:
+            - '~> 1 == 1'
+            - do:
+              - '~> 1'
+        long: '~> 1 + 1 + 1 + 1 + 1 + 1 + 1'
+        ";
+        let doc = engine.load_str(s).unwrap();
+        let mut env = engine.new_environment();
+        assert_eq!(
+            doc.eval_path("forever", &mut env),
+            Err(YamlError::EvalError(EvalError::IterationLimit(10)))
+        );
+        assert_eq!(
+            doc.eval_path("long", &mut env),
+            Err(YamlError::EvalError(EvalError::ExpressionTooLong(20)))
+        );
+
+        assert_eq!(
+            engine.load_str("a: [[[[[[1]]]]]]").err(),
+            Some(LoadError::TooDeep(6))
+        );
+        assert!(matches!(
+            engine.load_file("does/not/exist.yaml").err(),
+            Some(LoadError::Io(_))
+        ));
+    }
+}
@@ -1,5 +1,10 @@
-use crate::ast::lit::Lit;
-use std::collections::HashMap;
+use crate::ast::lit::{Lit, LitType};
+use crate::errors::LoadError;
+use crate::json::to_json;
+use linked_hash_map::LinkedHashMap;
+use std::collections::{HashMap, HashSet};
+use yaml_rust::yaml::Yaml;
+use yaml_rust::{YamlEmitter, YamlLoader};
 
 pub trait Environment {
     /// Gets a value from the environment
@@ -13,12 +18,42 @@ pub trait Environment {
     /// the current scope whereas assign assigns to the most current scope
     /// of an existing variable (so it can assign to variables in previous scopes if there
     /// is no binding of a variable to the current scope)
-    fn assign(&mut self, var: &str, value: Lit);
+    /// returns true if the variable was found and is not read-only and false otherwise
+    fn assign(&mut self, var: &str, value: Lit) -> bool;
 
     /// Sets a binding from variable name to a value in the current scope
     /// of an environment
     fn set(&mut self, var: &str, value: Lit);
 
+    /// Sets a read-only binding from variable name to a value in the current scope
+    /// of an environment (assign will not modify it)
+    fn set_const(&mut self, var: &str, value: Lit);
+
+    /// Returns true if the most current binding of the variable is read-only
+    fn is_const(&self, var: &str) -> bool;
+
+    /// Returns true if the variable is bound to a read-only value in the current scope
+    /// (a declaration in an inner scope shadows a read-only variable of an outer scope)
+    fn is_const_in_scope(&self, var: &str) -> bool {
+        let depth = self.len().saturating_sub(1);
+        self.bindings()
+            .iter()
+            .any(|binding| binding.name == var && binding.constant && binding.depth == depth)
+    }
+
+    /// Returns true if the variable is bound in the current scope or a previous one
+    fn contains(&self, var: &str) -> bool {
+        self.get(var).is_some()
+    }
+
+    /// Removes the most current binding of a variable (revealing the
+    /// binding it shadowed if there is one)
+    /// returns the removed value or None if the variable is not bound
+    fn remove(&mut self, var: &str) -> Option<Lit>;
+
+    /// Returns the bindings visible from the current scope sorted by name
+    fn bindings(&self) -> Vec<BindingInfo>;
+
     /// Adds a new scope to the environment
     /// used for blocks like if statements or for loops
     fn push(&mut self);
@@ -32,20 +67,397 @@ pub trait Environment {
 
     /// Returns true if there are no more scopes in the environment and false otherwise
     fn is_empty(&self) -> bool;
+
+    /// Returns true if assigning to an undeclared variable should be
+    /// silently ignored instead of being an error (for legacy documents)
+    fn is_lenient(&self) -> bool {
+        false
+    }
+
+    /// Records the current state of the environment so the changes
+    /// made after it can be undone with restore or kept with commit
+    fn snapshot(&mut self) -> Snapshot;
+
+    /// Undoes every change made since the snapshot was taken, closing the
+    /// snapshot and every snapshot taken after it
+    /// returns false if the snapshot was already restored or committed
+    fn restore(&mut self, snapshot: Snapshot) -> bool;
+
+    /// Keeps the changes made since the snapshot was taken, closing the
+    /// snapshot and every snapshot taken after it
+    /// returns false if the snapshot was already restored or committed
+    fn commit(&mut self, snapshot: Snapshot) -> bool;
+
+    /// Returns the changes recorded by the environment
+    /// or None if it is not tracking changes
+    fn change_log(&self) -> Option<&ChangeLog> {
+        None
+    }
+
+    /// Same as change_log but mutable (used to attribute the
+    /// changes to the expression making them)
+    fn change_log_mut(&mut self) -> Option<&mut ChangeLog> {
+        None
+    }
+}
+
+/// Describes a binding visible from the current scope of an environment
+#[derive(Debug, PartialEq, Clone)]
+pub struct BindingInfo {
+    pub name: String,
+    pub lit_type: LitType,
+    /// The scope the binding lives in (0 is the outermost scope)
+    pub depth: usize,
+    pub constant: bool,
+}
+
+/// A change made to a variable while the environment was tracking changes
+#[derive(Debug, PartialEq, Clone)]
+pub struct Change {
+    pub name: String,
+    /// The value before the change or None if the variable was not declared
+    pub old: Option<Lit>,
+    pub new: Lit,
+    /// The expression that made the change or None if it was made by the host
+    pub expression: Option<String>,
 }
 
+/// Records the changes made to the variables of an environment
+/// in the order they were made
+#[derive(Debug, Default, Clone)]
+pub struct ChangeLog {
+    changes: Vec<Change>,
+    expression: Option<String>,
+}
+
+impl ChangeLog {
+    pub fn new() -> ChangeLog {
+        ChangeLog::default()
+    }
+
+    /// Sets the expression that the next changes are attributed to
+    pub fn set_expression(&mut self, expression: Option<&str>) {
+        self.expression = expression.map(|e| e.trim().to_owned());
+    }
+
+    pub fn record(&mut self, name: &str, old: Option<Lit>, new: Lit) {
+        self.changes.push(Change {
+            name: name.to_owned(),
+            old,
+            new,
+            expression: self.expression.clone(),
+        });
+    }
+
+    /// Returns every change recorded so far
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+
+    /// Returns one change per variable going from the value before its first
+    /// change to the value after its last change (made by the last expression)
+    /// leaving out variables that ended up with the value they started with
+    pub fn diff(&self) -> Vec<Change> {
+        let mut diff: Vec<Change> = Vec::new();
+        for change in &self.changes {
+            match diff.iter_mut().find(|c| c.name == change.name) {
+                Some(c) => {
+                    c.new = change.new.clone();
+                    c.expression = change.expression.clone();
+                }
+                None => diff.push(change.clone()),
+            }
+        }
+
+        diff.retain(|c| c.old.as_ref() != Some(&c.new));
+        diff
+    }
+}
+
+/// A marker for the state of an environment at some point that can be
+/// restored later (like a transaction)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Snapshot {
+    id: u64,
+}
+
+/// Records how to undo the changes made to an environment while there
+/// are open snapshots
+#[derive(Clone)]
+pub(crate) struct Journal<U> {
+    entries: Vec<U>,
+    /// The id and journal position of each open snapshot (oldest first)
+    open: Vec<(u64, usize)>,
+    next_id: u64,
+}
+
+impl<U> Journal<U> {
+    pub fn new() -> Journal<U> {
+        Journal {
+            entries: Vec::new(),
+            open: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns true if changes need to be recorded
+    pub fn is_recording(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    /// Records how to undo a change if there are open snapshots
+    pub fn record(&mut self, undo: U) {
+        if self.is_recording() {
+            self.entries.push(undo);
+        }
+    }
+
+    pub fn snapshot(&mut self) -> Snapshot {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.open.push((id, self.entries.len()));
+        Snapshot { id }
+    }
+
+    // closes the snapshot and the snapshots opened after it
+    // returns the journal position of the snapshot or None if it is not open
+    fn close(&mut self, snapshot: Snapshot) -> Option<usize> {
+        let index = self.open.iter().position(|&(id, _)| id == snapshot.id)?;
+        let position = self.open[index].1;
+        self.open.truncate(index);
+        Some(position)
+    }
+
+    /// Closes the snapshot and returns the changes made since it was taken
+    /// (most recent first) or None if the snapshot is not open
+    pub fn rollback(&mut self, snapshot: Snapshot) -> Option<Vec<U>> {
+        let position = self.close(snapshot)?;
+        let mut undos = self.entries.split_off(position);
+        undos.reverse();
+        Some(undos)
+    }
+
+    /// Closes the snapshot keeping the changes made since it was taken
+    /// returns false if the snapshot is not open
+    pub fn commit(&mut self, snapshot: Snapshot) -> bool {
+        if self.close(snapshot).is_none() {
+            return false;
+        }
+
+        if !self.is_recording() {
+            self.entries.clear();
+        }
+        true
+    }
+}
+
+#[derive(Clone)]
+struct Binding {
+    value: Lit,
+    constant: bool,
+}
+
+#[derive(Clone)]
+enum Undo {
+    /// Puts back the previous binding of a name in a scope
+    Binding(usize, String, Option<Binding>),
+    /// Removes a pushed scope
+    Push,
+    /// Adds back a popped scope
+    Pop(HashMap<String, Binding>),
+}
+
+#[derive(Clone)]
 pub struct ASTEnvironment {
-    stack: Vec<HashMap<String, Lit>>,
+    stack: Vec<HashMap<String, Binding>>,
+    lenient: bool,
+    journal: Journal<Undo>,
+    changes: Option<ChangeLog>,
 }
 
 impl ASTEnvironment {
     pub fn new() -> ASTEnvironment {
         ASTEnvironment {
             stack: vec![HashMap::new()],
+            lenient: false,
+            journal: Journal::new(),
+            changes: None,
+        }
+    }
+
+    /// Creates an environment where assigning to an undeclared
+    /// variable is ignored instead of being an error
+    pub fn lenient() -> ASTEnvironment {
+        ASTEnvironment {
+            lenient: true,
+            ..ASTEnvironment::new()
+        }
+    }
+
+    /// Turns lenient assignment on or off
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Turns recording changes to variables on or off
+    /// (turning it off discards the recorded changes)
+    pub fn set_track_changes(&mut self, track: bool) {
+        match (track, &self.changes) {
+            (true, None) => self.changes = Some(ChangeLog::new()),
+            (false, _) => self.changes = None,
+            _ => {}
+        }
+    }
+
+    /// Converts every scope of the environment (outermost first) to YAML:
+    ///
+    /// ```yaml
+    /// scopes:
+    ///   - variables:
+    ///       hello: 2
+    ///     constants:
+    ///       season: spring
+    /// ```
+    pub fn to_yaml(&self) -> Yaml {
+        let scopes = self
+            .stack
+            .iter()
+            .map(|scope| {
+                let mut names = scope.keys().collect::<Vec<_>>();
+                names.sort();
+
+                let (mut variables, mut constants) = (LinkedHashMap::new(), LinkedHashMap::new());
+                for name in names {
+                    let binding = &scope[name];
+                    let hash = if binding.constant {
+                        &mut constants
+                    } else {
+                        &mut variables
+                    };
+                    hash.insert(Yaml::String(name.clone()), saved_value(&binding.value));
+                }
+
+                let mut h = LinkedHashMap::new();
+                h.insert(Yaml::String("variables".to_owned()), Yaml::Hash(variables));
+                h.insert(Yaml::String("constants".to_owned()), Yaml::Hash(constants));
+                Yaml::Hash(h)
+            })
+            .collect();
+
+        let mut h = LinkedHashMap::new();
+        h.insert(Yaml::String("scopes".to_owned()), Yaml::Array(scopes));
+        Yaml::Hash(h)
+    }
+
+    /// Same as to_yaml but emitted as a YAML string
+    pub fn to_yaml_string(&self) -> String {
+        let mut s = String::new();
+        YamlEmitter::new(&mut s).dump(&self.to_yaml()).unwrap();
+        s
+    }
+
+    /// Same as to_yaml but written as a JSON string
+    pub fn to_json_string(&self) -> String {
+        to_json(&self.to_yaml())
+    }
+
+    /// Creates an environment from YAML in the format written by to_yaml
+    /// or from a mapping of variable names to values (put in a single scope)
+    pub fn from_yaml(yaml: &Yaml) -> Result<ASTEnvironment, LoadError> {
+        let mut env = ASTEnvironment::new();
+
+        match yaml["scopes"] {
+            Yaml::Array(ref scopes) => {
+                for (i, scope) in scopes.iter().enumerate() {
+                    if i > 0 {
+                        env.push();
+                    }
+                    env.load_bindings(&scope["variables"], false)?;
+                    env.load_bindings(&scope["constants"], true)?;
+                }
+            }
+            _ => env.load_bindings(yaml, false)?,
+        }
+
+        Ok(env)
+    }
+
+    /// Same as from_yaml but parses the YAML (or JSON) from a string
+    pub fn from_yaml_str(s: &str) -> Result<ASTEnvironment, LoadError> {
+        let docs = YamlLoader::load_from_str(s).map_err(|e| LoadError::Syntax(e.to_string()))?;
+        match docs.first() {
+            Some(doc) => ASTEnvironment::from_yaml(doc),
+            None => Ok(ASTEnvironment::new()),
+        }
+    }
+
+    // sets the bindings of a mapping of variable names to values in the current scope
+    fn load_bindings(&mut self, yaml: &Yaml, constant: bool) -> Result<(), LoadError> {
+        let h = match *yaml {
+            Yaml::Hash(ref h) => h,
+            // missing or empty (variables:) sections have no bindings
+            Yaml::BadValue | Yaml::Null => return Ok(()),
+            _ => return Err(LoadError::NotMapping),
+        };
+
+        for (key, val) in h {
+            let name = match *key {
+                Yaml::String(ref name) => name,
+                _ => return Err(LoadError::NameNotString),
+            };
+            let value =
+                Lit::from_yaml(val).ok_or_else(|| LoadError::UnsupportedValue(name.clone()))?;
+
+            if constant {
+                self.set_const(name, value);
+            } else {
+                self.set(name, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as get but borrows the value instead of cloning it
+    pub fn get_ref(&self, var: &str) -> Option<&Lit> {
+        for i in (0..self.stack.len()).rev() {
+            if let Some(binding) = self.stack[i].get(var) {
+                return Some(&binding.value);
+            }
+        }
+
+        None
+    }
+
+    fn bind(&mut self, var: &str, binding: Binding) {
+        if self.changes.is_some() {
+            let old = self.get(var);
+            if let Some(log) = self.changes.as_mut() {
+                log.record(var, old, binding.value.clone());
+            }
+        }
+
+        if let Some(scope) = self.stack.last_mut() {
+            let previous = scope.insert(var.to_owned(), binding);
+            let depth = self.stack.len() - 1;
+            self.journal
+                .record(Undo::Binding(depth, var.to_owned(), previous));
         }
     }
 }
 
+// the value of a binding written by to_yaml, a whole real keeps its fraction
+// (2.0 instead of the 2 evaluating returns) so it is loaded back as a real
+fn saved_value(lit: &Lit) -> Yaml {
+    match *lit {
+        Lit::Decimal(d) => Yaml::Real(format!("{:?}", d)),
+        ref lit => lit.clone().into(),
+    }
+}
+
 impl Default for ASTEnvironment {
     fn default() -> Self {
         Self::new()
@@ -54,37 +466,115 @@ impl Default for ASTEnvironment {
 
 impl Environment for ASTEnvironment {
     fn get(&self, var: &str) -> Option<Lit> {
+        self.get_ref(var).cloned()
+    }
+
+    fn contains(&self, var: &str) -> bool {
+        self.get_ref(var).is_some()
+    }
+
+    fn assign(&mut self, var: &str, value: Lit) -> bool {
         for i in (0..self.stack.len()).rev() {
-            if let Some(val) = self.stack[i].get(var) {
-                return Some(val.clone());
+            if let Some(binding) = self.stack[i].get_mut(var) {
+                if binding.constant {
+                    return false;
+                }
+
+                if self.journal.is_recording() {
+                    let previous = Some(binding.clone());
+                    self.journal
+                        .record(Undo::Binding(i, var.to_owned(), previous));
+                }
+
+                if let Some(log) = self.changes.as_mut() {
+                    log.record(var, Some(binding.value.clone()), value.clone());
+                }
+
+                binding.value = value;
+                return true;
             }
         }
 
-        None
+        false
     }
 
-    fn assign(&mut self, var: &str, value: Lit) {
+    fn set(&mut self, var: &str, value: Lit) {
+        self.bind(
+            var,
+            Binding {
+                value,
+                constant: false,
+            },
+        );
+    }
+
+    fn set_const(&mut self, var: &str, value: Lit) {
+        self.bind(
+            var,
+            Binding {
+                value,
+                constant: true,
+            },
+        );
+    }
+
+    fn is_const(&self, var: &str) -> bool {
         for i in (0..self.stack.len()).rev() {
-            if self.stack[i].contains_key(var) {
-                *self.stack[i].get_mut(var).unwrap() = value;
-                return;
+            if let Some(binding) = self.stack[i].get(var) {
+                return binding.constant;
             }
         }
+
+        false
     }
 
-    fn set(&mut self, var: &str, value: Lit) {
-        let n = self.stack.len();
-        if n > 0 {
-            self.stack[n - 1].insert(var.to_owned(), value);
+    fn is_const_in_scope(&self, var: &str) -> bool {
+        matches!(self.stack.last().and_then(|scope| scope.get(var)), Some(binding) if binding.constant)
+    }
+
+    fn remove(&mut self, var: &str) -> Option<Lit> {
+        for i in (0..self.stack.len()).rev() {
+            if let Some(binding) = self.stack[i].remove(var) {
+                let value = binding.value.clone();
+                self.journal
+                    .record(Undo::Binding(i, var.to_owned(), Some(binding)));
+                return Some(value);
+            }
         }
+
+        None
+    }
+
+    fn bindings(&self) -> Vec<BindingInfo> {
+        let mut bindings: Vec<BindingInfo> = Vec::new();
+        // the innermost binding of a name shadows the ones in outer scopes
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (depth, scope) in self.stack.iter().enumerate().rev() {
+            for (name, binding) in scope {
+                if seen.insert(name.as_str()) {
+                    bindings.push(BindingInfo {
+                        name: name.clone(),
+                        lit_type: binding.value.lit_type(),
+                        depth,
+                        constant: binding.constant,
+                    });
+                }
+            }
+        }
+
+        bindings.sort_by(|a, b| a.name.cmp(&b.name));
+        bindings
     }
 
     fn push(&mut self) {
         self.stack.push(HashMap::new());
+        self.journal.record(Undo::Push);
     }
 
     fn pop(&mut self) {
-        self.stack.pop();
+        if let Some(scope) = self.stack.pop() {
+            self.journal.record(Undo::Pop(scope));
+        }
     }
 
     fn len(&self) -> usize {
@@ -94,12 +584,56 @@ impl Environment for ASTEnvironment {
     fn is_empty(&self) -> bool {
         self.stack.len() == 0
     }
+
+    fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    fn snapshot(&mut self) -> Snapshot {
+        self.journal.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) -> bool {
+        let undos = match self.journal.rollback(snapshot) {
+            Some(undos) => undos,
+            None => return false,
+        };
+
+        for undo in undos {
+            match undo {
+                Undo::Binding(depth, var, Some(binding)) => {
+                    self.stack[depth].insert(var, binding);
+                }
+                Undo::Binding(depth, var, None) => {
+                    self.stack[depth].remove(&var);
+                }
+                Undo::Push => {
+                    self.stack.pop();
+                }
+                Undo::Pop(scope) => self.stack.push(scope),
+            }
+        }
+        true
+    }
+
+    fn commit(&mut self, snapshot: Snapshot) -> bool {
+        self.journal.commit(snapshot)
+    }
+
+    fn change_log(&self) -> Option<&ChangeLog> {
+        self.changes.as_ref()
+    }
+
+    fn change_log_mut(&mut self) -> Option<&mut ChangeLog> {
+        self.changes.as_mut()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ASTEnvironment, Environment};
-    use crate::ast::Lit;
+    use super::{ASTEnvironment, BindingInfo, Change, Environment};
+    use crate::ast::{Lit, LitType};
+    use crate::errors::LoadError;
 
     #[test]
     fn test_bad_value_empty_stack() {
@@ -166,9 +700,200 @@ mod tests {
         let mut env = ASTEnvironment::new();
         env.set("hello", Lit::Number(2));
         env.push();
-        env.assign("hello", Lit::Number(3));
+        assert!(env.assign("hello", Lit::Number(3)));
+        env.pop();
+
+        assert_eq!(env.get("hello"), Some(Lit::Number(3)));
+    }
+
+    #[test]
+    fn test_assign_does_not_modify_const() {
+        let mut env = ASTEnvironment::new();
+        env.set_const("season", Lit::Str("spring".to_owned()));
+        env.push();
+
+        assert!(env.is_const("season"));
+        assert!(!env.assign("season", Lit::Str("winter".to_owned())));
+        assert_eq!(env.get("season"), Some(Lit::Str("spring".to_owned())));
+
+        env.set("season", Lit::Str("fall".to_owned()));
+        assert!(!env.is_const("season"));
+        env.pop();
+        assert!(env.is_const("season"));
+    }
+
+    #[test]
+    fn test_restore_snapshot() {
+        let mut env = ASTEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.push();
+        env.set("world", Lit::Number(3));
+
+        let snapshot = env.snapshot();
+        env.assign("hello", Lit::Number(4));
+        env.set("world", Lit::Number(5));
         env.pop();
+        env.set("blah", Lit::Number(6));
+        env.push();
+        env.push();
+
+        assert!(env.restore(snapshot));
+        assert_eq!(env.len(), 2);
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert_eq!(env.get("world"), Some(Lit::Number(3)));
+        assert_eq!(env.get("blah"), None);
 
+        // a snapshot can only be restored once
+        assert!(!env.restore(snapshot));
+    }
+
+    #[test]
+    fn test_nested_snapshots() {
+        let mut env = ASTEnvironment::new();
+        env.set("hello", Lit::Number(1));
+
+        let outer = env.snapshot();
+        env.assign("hello", Lit::Number(2));
+        let inner = env.snapshot();
+        env.assign("hello", Lit::Number(3));
+
+        assert!(env.commit(inner));
         assert_eq!(env.get("hello"), Some(Lit::Number(3)));
+
+        // restoring the outer snapshot also undoes the committed inner changes
+        assert!(env.restore(outer));
+        assert_eq!(env.get("hello"), Some(Lit::Number(1)));
+        assert!(!env.commit(inner));
+    }
+
+    #[test]
+    fn test_change_log() {
+        let mut env = ASTEnvironment::new();
+        env.set("hello", Lit::Number(1));
+        assert!(env.change_log().is_none());
+
+        env.set_track_changes(true);
+        env.change_log_mut()
+            .unwrap()
+            .set_expression(Some(" hello = 2 "));
+        env.assign("hello", Lit::Number(2));
+        env.change_log_mut().unwrap().set_expression(None);
+        env.set("world", Lit::Number(3));
+        env.assign("hello", Lit::Number(1));
+
+        let log = env.change_log().unwrap();
+        assert_eq!(log.changes().len(), 3);
+        assert_eq!(
+            log.changes()[0],
+            Change {
+                name: "hello".to_owned(),
+                old: Some(Lit::Number(1)),
+                new: Lit::Number(2),
+                expression: Some("hello = 2".to_owned()),
+            }
+        );
+
+        // hello ended up with its original value so only world changed
+        assert_eq!(
+            log.diff(),
+            vec![Change {
+                name: "world".to_owned(),
+                old: None,
+                new: Lit::Number(3),
+                expression: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_remove_reveals_shadowed_binding() {
+        let mut env = ASTEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.push();
+        env.set("hello", Lit::Str("world".to_owned()));
+
+        assert_eq!(env.remove("hello"), Some(Lit::Str("world".to_owned())));
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert_eq!(env.remove("hello"), Some(Lit::Number(2)));
+        assert!(!env.contains("hello"));
+        assert_eq!(env.remove("hello"), None);
+    }
+
+    #[test]
+    fn test_bindings() {
+        let mut env = ASTEnvironment::new();
+        env.set_const("season", Lit::Str("spring".to_owned()));
+        env.set("hello", Lit::Number(2));
+        env.push();
+        env.set("hello", Lit::Decimal(2.5));
+
+        assert_eq!(
+            env.bindings(),
+            vec![
+                BindingInfo {
+                    name: "hello".to_owned(),
+                    lit_type: LitType::Decimal,
+                    depth: 1,
+                    constant: false,
+                },
+                BindingInfo {
+                    name: "season".to_owned(),
+                    lit_type: LitType::Str,
+                    depth: 0,
+                    constant: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let mut env = ASTEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.set_const("season", Lit::Str("spring".to_owned()));
+        env.push();
+        env.set("hello", Lit::Decimal(2.0));
+        env.set("nearby", Lit::Bool(true));
+
+        assert_eq!(
+            env.to_json_string(),
+            r#"{"scopes":[{"variables":{"hello":2},"constants":{"season":"spring"}},"#.to_owned()
+                + r#"{"variables":{"hello":2.0,"nearby":true},"constants":{}}]}"#
+        );
+
+        for s in [env.to_yaml_string(), env.to_json_string()] {
+            let mut loaded = ASTEnvironment::from_yaml_str(&s).unwrap();
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded.bindings(), env.bindings());
+
+            loaded.pop();
+            assert_eq!(loaded.get("hello"), Some(Lit::Number(2)));
+            assert!(loaded.is_const("season"));
+        }
+    }
+
+    #[test]
+    fn test_load_mapping() {
+        let env = ASTEnvironment::from_yaml_str("hello: 2\nworld: ~").unwrap();
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert_eq!(env.get("world"), Some(Lit::Nil));
+
+        assert_eq!(
+            ASTEnvironment::from_yaml_str("hello: [1, 2]").err(),
+            Some(LoadError::UnsupportedValue("hello".to_owned()))
+        );
+        assert_eq!(
+            ASTEnvironment::from_yaml_str("- 1").err(),
+            Some(LoadError::NotMapping)
+        );
+    }
+
+    #[test]
+    fn test_assign_undeclared_variable() {
+        let mut env = ASTEnvironment::new();
+        env.set("hello", Lit::Number(2));
+
+        assert!(!env.assign("world", Lit::Number(3)));
+        assert_eq!(env.get("world"), None);
     }
 }
@@ -4,13 +4,15 @@ use crate::ast::{Exp, Lit, Op};
 pub enum YamlError {
     LexError(LexError),
     EvalError(EvalError),
+    PathError(PathError),
 }
 
 impl YamlError {
-    fn description(&self) -> String {
+    pub fn description(&self) -> String {
         match *self {
             YamlError::LexError(ref err) => err.description().to_owned(),
             YamlError::EvalError(ref err) => err.description(),
+            YamlError::PathError(ref err) => err.description(),
         }
     }
 }
@@ -41,6 +43,9 @@ pub enum LexError {
     Incomplete,
     /// When the parenthesis do not match ("(1 + 2")
     ParenthesisNotMatch,
+    /// When const is not followed by a declaration
+    /// (const x = 2, const 1 + 2)
+    ConstNotDeclare,
 }
 
 impl LexError {
@@ -56,6 +61,7 @@ impl LexError {
             LexError::VariableStackError => "Variable cannot be retrieved from the stack",
             LexError::ParenthesisNotMatch => "Parenthesis do not match",
             LexError::Incomplete => "Lexer cannot completely parse expression",
+            LexError::ConstNotDeclare => "Const must be followed by a declaration",
         }
     }
 }
@@ -78,6 +84,28 @@ pub enum EvalError {
     CannotReduceDeclare(Box<Exp>),
     /// When a variable is not in an environment
     VarNotInEnv(String),
+    /// When assigning to a variable that was never declared
+    /// (a = 2 without a := 1 or a host binding)
+    AssignToUndeclared(String),
+    /// When assigning to or redeclaring a read-only variable
+    /// (const x := 1 followed by x = 2)
+    AssignToConst(String),
+    /// When an integer is divided by zero (5 / 0, 5 % 0)
+    DivisionByZero,
+    /// When a path to evaluate is not in the document
+    PathNotFound(String),
+    /// When a while loop runs more times than the limit of the engine
+    IterationLimit(usize),
+    /// When an expression is longer than the limit of the engine
+    ExpressionTooLong(usize),
+    /// When calling a function that was not registered
+    FunctionNotFound(String),
+    /// When an argument of a function cannot be reduced to a value
+    /// (an array or hash)
+    InvalidArgument(String),
+    /// When a function registered by the host returns an error
+    /// (the name of the function and the error)
+    FunctionFailed(String, String),
     /// When an operator is not a valid unary operator
     NotUnOp(Op),
     /// When an operator is not a valid binary operator
@@ -133,8 +161,139 @@ impl EvalError {
             EvalError::VarNotInEnv(ref name) => {
                 format!("Variable {:?} not in environment", name.clone())
             }
+            EvalError::AssignToUndeclared(ref name) => {
+                format!("Cannot assign to undeclared variable {:?}", name.clone())
+            }
+            EvalError::AssignToConst(ref name) => {
+                format!("Cannot assign to read-only variable {:?}", name.clone())
+            }
+            EvalError::DivisionByZero => "Division by zero".to_owned(),
+            EvalError::PathNotFound(ref path) => {
+                format!("Path {:?} not found in document", path.clone())
+            }
+            EvalError::IterationLimit(limit) => {
+                format!("While loop ran more than {} times", limit)
+            }
+            EvalError::ExpressionTooLong(limit) => {
+                format!("Expression is longer than {} bytes", limit)
+            }
+            EvalError::FunctionNotFound(ref name) => {
+                format!("Function {:?} is not registered", name.clone())
+            }
+            EvalError::InvalidArgument(ref name) => {
+                format!("Argument of function {:?} is not a value", name.clone())
+            }
+            EvalError::FunctionFailed(ref name, ref err) => {
+                format!("Function {:?} failed: {}", name.clone(), err)
+            }
             EvalError::NotUnOp(op) => format!("{:?} is not a unary operator", op),
             EvalError::NotBinOp(op) => format!("{:?} is not a binary operator", op),
         }
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    /// When the string to load from is not valid YAML
+    Syntax(String),
+    /// When the YAML to load from is not a mapping of variables
+    /// or a list of scopes
+    NotMapping,
+    /// When a variable name is not a string (1: hello)
+    NameNotString,
+    /// When a variable value cannot be stored in an environment
+    /// (hello: [1, 2])
+    UnsupportedValue(String),
+    /// When a file to load from cannot be read
+    Io(String),
+    /// When a document is nested deeper than the limit of the engine
+    TooDeep(usize),
+}
+
+impl LoadError {
+    pub fn description(&self) -> String {
+        match *self {
+            LoadError::Syntax(ref err) => format!("Invalid YAML: {}", err),
+            LoadError::NotMapping => {
+                "Environment must be a mapping of variables or a list of scopes".to_owned()
+            }
+            LoadError::NameNotString => "Variable name is not a string".to_owned(),
+            LoadError::UnsupportedValue(ref name) => {
+                format!("Value of variable {:?} is not a scalar", name.clone())
+            }
+            LoadError::Io(ref err) => format!("Cannot read file: {}", err),
+            LoadError::TooDeep(limit) => {
+                format!("Document is nested deeper than {} levels", limit)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PathError {
+    /// When a key in a path is empty (a..b, .a, a.)
+    EmptyKey,
+    /// When an array index is not a number (a[b], a[-1])
+    InvalidIndex(String),
+    /// When a bracket is not closed (a[1)
+    UnclosedBracket,
+}
+
+impl PathError {
+    pub fn description(&self) -> String {
+        match *self {
+            PathError::EmptyKey => "Key in path is empty".to_owned(),
+            PathError::InvalidIndex(ref index) => {
+                format!("Index {:?} in path is not a number", index.clone())
+            }
+            PathError::UnclosedBracket => "Bracket in path is not closed".to_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq)]
+pub enum SerdeError {
+    /// When a value cannot be converted (reported by serde or
+    /// by the Serialize and Deserialize implementations)
+    Message(String),
+    /// When a value cannot be represented in YAML (integers larger than i64::MAX)
+    Unsupported(String),
+    /// When a value cannot be stored in an environment
+    LoadError(LoadError),
+}
+
+#[cfg(feature = "serde")]
+impl SerdeError {
+    pub fn description(&self) -> String {
+        match *self {
+            SerdeError::Message(ref message) => message.clone(),
+            SerdeError::Unsupported(ref value) => format!("Cannot convert {} to YAML", value),
+            SerdeError::LoadError(ref err) => err.description(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.description())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for SerdeError {}
+
+#[cfg(feature = "serde")]
+impl serde::ser::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> SerdeError {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> SerdeError {
+        SerdeError::Message(msg.to_string())
+    }
+}
@@ -1,12 +1,277 @@
-use crate::ast::Lit;
-use crate::environment::{ASTEnvironment, Environment};
-use crate::ffi::types::{Error, FFIReturnValue};
+use crate::ast::{Lit, LitType};
+use crate::environment::{ASTEnvironment, Environment, Snapshot};
+use crate::errors::{EvalError, LoadError};
+use crate::ffi::error::{LastError, set_last_error};
+use crate::ffi::guard::{c_string, guard, mut_arg, ref_arg, string_arg};
+use crate::ffi::memory::string_array;
+use crate::ffi::types::{Error, FFIArrayReturnValue, FFIChange, FFIReturnValue, YamlType};
 use libc::c_char;
-use std::ffi::{CStr, CString};
+use std::ffi::{CString, NulError};
+use std::ptr;
+use yaml_rust::yaml::Yaml;
+
+fn not_defined(key: &str) -> LastError {
+    LastError::new(
+        Error::NotDefined,
+        EvalError::VarNotInEnv(key.to_owned()).description(),
+    )
+}
+
+fn wrong_type(key: &str, value: &Lit) -> LastError {
+    LastError::new(
+        Error::WrongType,
+        format!("Variable {:?} has type {:?}", key, value.lit_type()),
+    )
+}
+
+fn snapshot_result(open: bool) -> Result<i32, LastError> {
+    if open {
+        Ok(Error::None as i32)
+    } else {
+        Err(LastError::new(
+            Error::NotDefined,
+            "Snapshot was already restored or committed",
+        ))
+    }
+}
 
 #[unsafe(no_mangle)]
 pub extern "C" fn environment_create() -> *mut ASTEnvironment {
-    Box::into_raw(Box::new(ASTEnvironment::new()))
+    guard(|| Ok(Box::into_raw(Box::new(ASTEnvironment::new()))))
+}
+
+/// Creates an environment from YAML or JSON written by environment_to_yaml or
+/// environment_to_json or from a mapping of variable names to values
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_create_from_string(
+    s: *const c_char,
+) -> FFIReturnValue<*mut ASTEnvironment> {
+    guard(|| {
+        let env_str = unsafe { string_arg(s) }?;
+
+        match ASTEnvironment::from_yaml_str(env_str.as_str()) {
+            Ok(environment) => Ok(FFIReturnValue {
+                value: Box::into_raw(Box::new(environment)),
+                error: Error::None as i32,
+            }),
+            Err(error @ LoadError::Syntax(_)) => {
+                Err(LastError::new(Error::ParseError, error.description()))
+            }
+            Err(error) => Err(LastError::new(Error::InvalidString, error.description())),
+        }
+    })
+}
+
+/// Returns every scope of the environment as YAML,
+/// the string must be freed with yamlate_string_free
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_to_yaml(env: *mut ASTEnvironment) -> *const c_char {
+    guard(|| Ok(c_string(unsafe { ref_arg(env) }?.to_yaml_string())?))
+}
+
+/// Returns every scope of the environment as JSON,
+/// the string must be freed with yamlate_string_free
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_to_json(env: *mut ASTEnvironment) -> *const c_char {
+    guard(|| Ok(c_string(unsafe { ref_arg(env) }?.to_json_string())?))
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_clone(env: *mut ASTEnvironment) -> *mut ASTEnvironment {
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+
+        Ok(Box::into_raw(Box::new(environment.clone())))
+    })
+}
+
+/// Starts recording changes to the environment, the returned snapshot
+/// must be passed to either environment_restore or environment_commit
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_snapshot(env: *mut ASTEnvironment) -> *mut Snapshot {
+    guard(|| {
+        let environment = unsafe { mut_arg(env) }?;
+
+        Ok(Box::into_raw(Box::new(environment.snapshot())))
+    })
+}
+
+/// Undoes every change made since the snapshot was taken and frees the snapshot
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_restore(
+    env: *mut ASTEnvironment,
+    snapshot: *mut Snapshot,
+) -> i32 {
+    guard(|| {
+        let environment = unsafe { mut_arg(env) }?;
+        unsafe { ref_arg(snapshot) }?;
+        let snapshot = unsafe { Box::from_raw(snapshot) };
+
+        snapshot_result(environment.restore(*snapshot))
+    })
+}
+
+/// Keeps every change made since the snapshot was taken and frees the snapshot
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_commit(
+    env: *mut ASTEnvironment,
+    snapshot: *mut Snapshot,
+) -> i32 {
+    guard(|| {
+        let environment = unsafe { mut_arg(env) }?;
+        unsafe { ref_arg(snapshot) }?;
+        let snapshot = unsafe { Box::from_raw(snapshot) };
+
+        snapshot_result(environment.commit(*snapshot))
+    })
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_lenient(env: *mut ASTEnvironment, lenient: bool) -> i32 {
+    guard(|| {
+        unsafe { mut_arg(env) }?.set_lenient(lenient);
+        Ok(Error::None as i32)
+    })
+}
+
+/// Turns recording the changes made to variables on or off
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_track_changes(
+    env: *mut ASTEnvironment,
+    track: bool,
+) -> i32 {
+    guard(|| {
+        unsafe { mut_arg(env) }?.set_track_changes(track);
+        Ok(Error::None as i32)
+    })
+}
+
+/// Returns the changes recorded so far in the order they were made,
+/// the list must be freed with environment_changes_destroy
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_changes(
+    env: *mut ASTEnvironment,
+) -> FFIArrayReturnValue<*const FFIChange> {
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+        let log = environment.change_log().ok_or_else(|| {
+            LastError::new(Error::NotDefined, "Environment is not tracking changes")
+        })?;
+
+        // every string is converted before anything is moved to C
+        // so a string containing a NUL does not leak the changes before it
+        let strings = log
+            .changes()
+            .iter()
+            .map(|change| {
+                let name = CString::new(change.name.as_str())?;
+                let expression = change.expression.as_deref().map(CString::new).transpose()?;
+                Ok((name, expression))
+            })
+            .collect::<Result<Vec<_>, NulError>>()
+            .map_err(|_| Error::InvalidString)?;
+
+        let yaml = |lit: Lit| Box::into_raw(Box::new(Yaml::from(lit))) as *const Yaml;
+        let changes = log
+            .changes()
+            .iter()
+            .zip(strings)
+            .map(|(change, (name, expression))| FFIChange {
+                name: name.into_raw(),
+                old_value: change.old.clone().map_or(ptr::null(), yaml),
+                new_value: yaml(change.new.clone()),
+                expression: expression.map_or(ptr::null(), |e| e.into_raw()),
+            })
+            .collect::<Box<[_]>>();
+        let length = changes.len();
+
+        Ok(FFIArrayReturnValue {
+            value: Box::into_raw(changes) as *const FFIChange,
+            length: length as i32,
+            error: Error::None as i32,
+        })
+    })
+}
+
+/// Frees a list of changes returned by environment_changes
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_changes_destroy(changes: *mut FFIChange, length: i32) {
+    if changes.is_null() || length < 0 {
+        return;
+    }
+
+    guard(|| {
+        let slice = ptr::slice_from_raw_parts_mut(changes, length as usize);
+        for change in unsafe { Box::from_raw(slice) }.iter() {
+            unsafe {
+                drop(CString::from_raw(change.name as *mut c_char));
+                drop(Box::from_raw(change.new_value as *mut Yaml));
+                if !change.old_value.is_null() {
+                    drop(Box::from_raw(change.old_value as *mut Yaml));
+                }
+                if !change.expression.is_null() {
+                    drop(CString::from_raw(change.expression as *mut c_char));
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Discards the changes recorded so far
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_clear_changes(env: *mut ASTEnvironment) -> i32 {
+    guard(|| {
+        if let Some(log) = unsafe { mut_arg(env) }?.change_log_mut() {
+            log.clear();
+        }
+        Ok(Error::None as i32)
+    })
+}
+
+/// Sets a variable from the arguments of one of the environment_set functions
+///
+/// # Safety
+unsafe fn set_variable(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+    value: impl FnOnce() -> Result<Lit, Error>,
+    constant: bool,
+) -> i32 {
+    guard(|| {
+        let environment = unsafe { mut_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
+        let value = value()?;
+
+        if constant {
+            environment.set_const(key.as_str(), value);
+        } else {
+            environment.set(key.as_str(), value);
+        }
+        Ok(Error::None as i32)
+    })
 }
 
 /// # Safety
@@ -15,11 +280,8 @@ pub unsafe extern "C" fn environment_set_integer(
     env: *mut ASTEnvironment,
     name: *const c_char,
     value: i32,
-) {
-    let environment = unsafe { env.as_mut().unwrap() };
-    let key: String = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
-
-    environment.set(key.as_str(), Lit::Number(value));
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Number(value)), false) }
 }
 
 /// # Safety
@@ -28,12 +290,9 @@ pub unsafe extern "C" fn environment_set_string(
     env: *mut ASTEnvironment,
     name: *const c_char,
     value: *const c_char,
-) {
-    let environment = unsafe { env.as_mut().unwrap() };
-    let key: String = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
-    let val: String = unsafe { CStr::from_ptr(value).to_string_lossy().into_owned() };
-
-    environment.set(key.as_str(), Lit::Str(val));
+) -> i32 {
+    let value = || Ok(Lit::Str(unsafe { string_arg(value) }?));
+    unsafe { set_variable(env, name, value, false) }
 }
 
 /// # Safety
@@ -42,11 +301,79 @@ pub unsafe extern "C" fn environment_set_decimal(
     env: *mut ASTEnvironment,
     name: *const c_char,
     value: f64,
-) {
-    let environment = unsafe { env.as_mut().unwrap() };
-    let key: String = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Decimal(value)), false) }
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_boolean(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+    value: bool,
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Bool(value)), false) }
+}
+
+/// Sets a variable to nil
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_null(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Nil), false) }
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_const_integer(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+    value: i32,
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Number(value)), true) }
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_const_string(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+    value: *const c_char,
+) -> i32 {
+    let value = || Ok(Lit::Str(unsafe { string_arg(value) }?));
+    unsafe { set_variable(env, name, value, true) }
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_const_decimal(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+    value: f64,
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Decimal(value)), true) }
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_const_boolean(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+    value: bool,
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Bool(value)), true) }
+}
 
-    environment.set(key.as_str(), Lit::Decimal(value));
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_set_const_null(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+) -> i32 {
+    unsafe { set_variable(env, name, || Ok(Lit::Nil), true) }
 }
 
 /// # Safety
@@ -55,52 +382,45 @@ pub unsafe extern "C" fn environment_get_integer(
     env: *mut ASTEnvironment,
     name: *const c_char,
 ) -> FFIReturnValue<i32> {
-    let environment = unsafe { env.as_ref().unwrap() };
-    let key: String = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
 
-    match environment.get(key.as_str()) {
-        Some(Lit::Number(val)) => FFIReturnValue {
-            value: val,
-            error: Error::None as i32,
-        },
-        Some(_) => FFIReturnValue {
-            value: 0,
-            error: Error::WrongType as i32,
-        },
-        None => FFIReturnValue {
-            value: 0,
-            error: Error::NotDefined as i32,
-        },
-    }
+        match environment.get_ref(key.as_str()) {
+            Some(&Lit::Number(val)) => Ok(FFIReturnValue {
+                value: val,
+                error: Error::None as i32,
+            }),
+            Some(value) => Err(wrong_type(&key, value)),
+            None => Err(not_defined(&key)),
+        }
+    })
 }
 
+/// The returned string (an empty string on error, null if an argument is null)
+/// must be freed with yamlate_string_free
+///
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn environment_get_string(
     env: *mut ASTEnvironment,
     name: *const c_char,
 ) -> FFIReturnValue<*const c_char> {
-    let environment = unsafe { env.as_ref().unwrap() };
-    let key: String = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
 
-    match environment.get(key.as_str()) {
-        Some(Lit::Str(ref val)) => {
-            let c_str = CString::new(val.clone().as_str()).unwrap().into_raw();
+        let (value, error) = match environment.get_ref(key.as_str()) {
+            Some(Lit::Str(val)) => (val.as_str(), Error::None as i32),
+            Some(value) => ("", set_last_error(wrong_type(&key, value))),
+            None => ("", set_last_error(not_defined(&key))),
+        };
 
-            FFIReturnValue {
-                value: c_str as *const c_char,
-                error: Error::None as i32,
-            }
-        }
-        Some(_) => FFIReturnValue {
-            value: CString::new("").unwrap().into_raw() as *const c_char,
-            error: Error::WrongType as i32,
-        },
-        None => FFIReturnValue {
-            value: CString::new("").unwrap().into_raw() as *const c_char,
-            error: Error::NotDefined as i32,
-        },
-    }
+        Ok(FFIReturnValue {
+            value: c_string(value)?,
+            error,
+        })
+    })
 }
 
 /// # Safety
@@ -109,28 +429,154 @@ pub unsafe extern "C" fn environment_get_decimal(
     env: *mut ASTEnvironment,
     name: *const c_char,
 ) -> FFIReturnValue<f64> {
-    let environment = unsafe { env.as_ref().unwrap() };
-    let key: String = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
+
+        match environment.get_ref(key.as_str()) {
+            Some(&Lit::Decimal(val)) => Ok(FFIReturnValue {
+                value: val,
+                error: Error::None as i32,
+            }),
+            Some(value) => Err(wrong_type(&key, value)),
+            None => Err(not_defined(&key)),
+        }
+    })
+}
 
-    match environment.get(key.as_str()) {
-        Some(Lit::Decimal(val)) => FFIReturnValue {
-            value: val,
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_get_boolean(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+) -> FFIReturnValue<bool> {
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
+
+        match environment.get_ref(key.as_str()) {
+            Some(&Lit::Bool(val)) => Ok(FFIReturnValue {
+                value: val,
+                error: Error::None as i32,
+            }),
+            Some(value) => Err(wrong_type(&key, value)),
+            None => Err(not_defined(&key)),
+        }
+    })
+}
+
+/// Returns the names of the variables visible from the current scope sorted by name,
+/// the array must be freed with yamlate_string_array_free
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_keys(
+    env: *mut ASTEnvironment,
+) -> FFIArrayReturnValue<*const *const c_char> {
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+
+        let keys = environment
+            .bindings()
+            .into_iter()
+            .map(|binding| binding.name);
+        let (value, length) = string_array(keys)?;
+
+        Ok(FFIArrayReturnValue {
+            value,
+            length,
             error: Error::None as i32,
-        },
-        Some(_) => FFIReturnValue {
-            value: 0.0,
-            error: Error::WrongType as i32,
-        },
-        None => FFIReturnValue {
-            value: 0.0,
-            error: Error::NotDefined as i32,
-        },
-    }
+        })
+    })
 }
 
+/// Returns the type of a variable as a YAML type
+/// (the same values returned by yaml_type)
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_type_of(
+    env: *mut ASTEnvironment,
+    name: *const c_char,
+) -> FFIReturnValue<i32> {
+    guard(|| {
+        let environment = unsafe { ref_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
+
+        match environment.get_ref(key.as_str()) {
+            Some(value) => Ok(FFIReturnValue {
+                value: match value.lit_type() {
+                    LitType::Number => YamlType::Integer as i32,
+                    LitType::Decimal => YamlType::Real as i32,
+                    LitType::Str => YamlType::String as i32,
+                    LitType::Bool => YamlType::Boolean as i32,
+                    LitType::Nil => YamlType::Null as i32,
+                },
+                error: Error::None as i32,
+            }),
+            None => Ok(FFIReturnValue {
+                value: YamlType::Null as i32,
+                error: set_last_error(not_defined(&key)),
+            }),
+        }
+    })
+}
+
+/// Removes the most current binding of a variable
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn environment_remove(env: *mut ASTEnvironment, name: *const c_char) -> i32 {
+    guard(|| {
+        let environment = unsafe { mut_arg(env) }?;
+        let key = unsafe { string_arg(name) }?;
+
+        match environment.remove(key.as_str()) {
+            Some(_) => Ok(Error::None as i32),
+            None => Err(not_defined(&key)),
+        }
+    })
+}
+
+/// Does nothing if the environment is null
+///
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn environment_destroy(env: *mut ASTEnvironment) {
-    assert!(!env.is_null());
-    drop(unsafe { Box::from_raw(env) })
+    if !env.is_null() {
+        guard(|| {
+            drop(unsafe { Box::from_raw(env) });
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_changes() {
+        unsafe {
+            let env = environment_create();
+            environment_set_track_changes(env, true);
+            environment_set_integer(env, c"legs".as_ptr(), 6);
+
+            let changes = environment_changes(env);
+            assert_eq!(changes.error, Error::None as i32);
+            assert_eq!(changes.length, 1);
+            let change = &*changes.value;
+            assert_eq!(CStr::from_ptr(change.name).to_str(), Ok("legs"));
+            assert!(change.old_value.is_null() && change.expression.is_null());
+            environment_changes_destroy(changes.value as *mut FFIChange, changes.length);
+
+            // a name that cannot be returned to C fails the whole list
+            (*env).set("wing\0color", Lit::Str("red".to_owned()));
+            let changes = environment_changes(env);
+            assert_eq!(changes.error, Error::InvalidString as i32);
+            assert!(changes.value.is_null());
+            environment_destroy(env);
+        }
+    }
 }
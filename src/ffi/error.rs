@@ -0,0 +1,155 @@
+use crate::ffi::guard::{c_string, guard};
+use crate::ffi::types::Error;
+use libc::c_char;
+use std::cell::RefCell;
+use std::ptr;
+
+/// An error reported by a FFI function with a description of what went wrong
+/// and the path of the document it happened at if there is one
+pub(crate) struct LastError {
+    code: Error,
+    message: String,
+    path: Option<String>,
+}
+
+impl LastError {
+    pub(crate) fn new(code: Error, message: impl Into<String>) -> LastError {
+        LastError {
+            code,
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    pub(crate) fn with_path(self, path: &str) -> LastError {
+        LastError {
+            path: Some(path.to_owned()),
+            ..self
+        }
+    }
+
+    pub(crate) fn code(&self) -> Error {
+        self.code
+    }
+}
+
+impl From<Error> for LastError {
+    fn from(code: Error) -> LastError {
+        LastError::new(code, code.description())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<LastError>> = const { RefCell::new(None) };
+}
+
+/// Records the error as the last error of the thread and returns its code
+pub(crate) fn set_last_error(error: impl Into<LastError>) -> i32 {
+    let error = error.into();
+    let code = error.code as i32;
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(error));
+    code
+}
+
+/// Returns the code of the last error reported on the calling thread,
+/// 0 if no function failed on the thread yet
+///
+/// Errors are not cleared by functions that succeed so the
+/// last error is only meaningful right after a function failed
+#[unsafe(no_mangle)]
+pub extern "C" fn yamlate_last_error_code() -> i32 {
+    LAST_ERROR.with(|last| match *last.borrow() {
+        Some(ref error) => error.code as i32,
+        None => Error::None as i32,
+    })
+}
+
+/// Returns the description of the last error reported on the calling thread
+/// (like "Variable \"x\" not in environment"), null if no function failed on
+/// the thread yet, the string must be freed with yamlate_string_free
+#[unsafe(no_mangle)]
+pub extern "C" fn yamlate_last_error_message() -> *const c_char {
+    guard(|| {
+        LAST_ERROR.with(|last| match *last.borrow() {
+            Some(ref error) => Ok(c_string(error.message.replace('\0', "\\0"))?),
+            None => Ok(ptr::null()),
+        })
+    })
+}
+
+/// Returns the path of the document the last error reported on the calling
+/// thread happened at (for the functions evaluating a path), null if there is
+/// no path, the string must be freed with yamlate_string_free
+#[unsafe(no_mangle)]
+pub extern "C" fn yamlate_last_error_path() -> *const c_char {
+    guard(|| {
+        LAST_ERROR.with(|last| match *last.borrow() {
+            Some(LastError {
+                path: Some(ref path),
+                ..
+            }) => Ok(c_string(path.replace('\0', "\\0"))?),
+            _ => Ok(ptr::null()),
+        })
+    })
+}
+
+/// Forgets the last error reported on the calling thread
+#[unsafe(no_mangle)]
+pub extern "C" fn yamlate_clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::environment::{environment_create, environment_destroy};
+    use crate::ffi::memory::yamlate_string_free;
+    use crate::ffi::yaml::{yaml_create_from_string, yaml_destroy, yaml_evaluate_path};
+    use std::ffi::CStr;
+    use std::thread;
+    use yaml_rust::yaml::Yaml;
+
+    fn take(s: *const c_char) -> Option<String> {
+        if s.is_null() {
+            return None;
+        }
+        let owned = unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned();
+        unsafe { yamlate_string_free(s as *mut c_char) };
+        Some(owned)
+    }
+
+    #[test]
+    fn test_last_error() {
+        unsafe {
+            let doc = yaml_create_from_string(c"foo:\n  bar: '~> x + 1'\n".as_ptr()).value;
+            let env = environment_create();
+
+            let result = yaml_evaluate_path(doc, c"foo.bar".as_ptr(), env);
+            assert_eq!(result.error, Error::EvalError as i32);
+            assert_eq!(yamlate_last_error_code(), Error::EvalError as i32);
+            assert_eq!(
+                take(yamlate_last_error_message()),
+                Some("Variable \"x\" not in environment".to_owned())
+            );
+            assert_eq!(take(yamlate_last_error_path()), Some("foo.bar".to_owned()));
+
+            // the last error belongs to the thread that caused it
+            thread::spawn(|| assert_eq!(yamlate_last_error_code(), Error::None as i32))
+                .join()
+                .unwrap();
+
+            let parsed = yaml_create_from_string(c"foo: [1".as_ptr());
+            assert_eq!(parsed.error, Error::ParseError as i32);
+            let message = take(yamlate_last_error_message()).unwrap();
+            assert!(message.contains("line"), "{}", message);
+            assert_eq!(take(yamlate_last_error_path()), None);
+
+            yamlate_clear_last_error();
+            assert_eq!(yamlate_last_error_code(), Error::None as i32);
+            assert!(yamlate_last_error_message().is_null());
+
+            environment_destroy(env);
+            yaml_destroy(doc as *mut Yaml);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::ffi::error::{LastError, set_last_error};
+use crate::ffi::types::{Error, FFIArrayReturnValue, FFIReturnValue};
+use libc::c_char;
+use std::ffi::{CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// A value returned in the value field of a FFI return value that failed
+pub(crate) trait Empty {
+    fn empty() -> Self;
+}
+
+impl Empty for i32 {
+    fn empty() -> i32 {
+        0
+    }
+}
+
+impl Empty for i64 {
+    fn empty() -> i64 {
+        0
+    }
+}
+
+impl Empty for f64 {
+    fn empty() -> f64 {
+        0.0
+    }
+}
+
+impl Empty for bool {
+    fn empty() -> bool {
+        false
+    }
+}
+
+impl<T> Empty for *const T {
+    fn empty() -> *const T {
+        ptr::null()
+    }
+}
+
+impl<T> Empty for *mut T {
+    fn empty() -> *mut T {
+        ptr::null_mut()
+    }
+}
+
+/// The value returned by a FFI function that failed
+pub(crate) trait Failure {
+    fn failure(error: Error) -> Self;
+}
+
+/// Functions returning only an error code return the error
+impl Failure for i32 {
+    fn failure(error: Error) -> i32 {
+        error as i32
+    }
+}
+
+/// Functions returning nothing (like the destroy functions) ignore the error
+impl Failure for () {
+    fn failure(_: Error) {}
+}
+
+/// Functions returning only a handle return null
+impl<T> Failure for *const T {
+    fn failure(_: Error) -> *const T {
+        ptr::null()
+    }
+}
+
+impl<T> Failure for *mut T {
+    fn failure(_: Error) -> *mut T {
+        ptr::null_mut()
+    }
+}
+
+impl<T: Empty> Failure for FFIReturnValue<T> {
+    fn failure(error: Error) -> FFIReturnValue<T> {
+        FFIReturnValue {
+            value: T::empty(),
+            error: error as i32,
+        }
+    }
+}
+
+impl<T: Empty> Failure for FFIArrayReturnValue<T> {
+    fn failure(error: Error) -> FFIArrayReturnValue<T> {
+        FFIArrayReturnValue {
+            value: T::empty(),
+            length: 0,
+            error: error as i32,
+        }
+    }
+}
+
+/// Runs the body of a FFI function, turning an error or a panic into the
+/// failure value of the function so a panic never unwinds into the caller
+///
+/// The error or panic is recorded as the last error of the thread
+pub(crate) fn guard<R: Failure>(body: impl FnOnce() -> Result<R, LastError>) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(error)) => {
+            let code = error.code();
+            set_last_error(error);
+            R::failure(code)
+        }
+        Err(payload) => {
+            let message = match payload.downcast_ref::<&str>() {
+                Some(message) => message,
+                None => payload.downcast_ref::<String>().map_or("", String::as_str),
+            };
+            set_last_error(LastError::new(
+                Error::Panic,
+                format!("{}: {}", Error::Panic.description(), message),
+            ));
+            R::failure(Error::Panic)
+        }
+    }
+}
+
+/// Copies a string argument
+///
+/// # Safety
+/// The pointer must be null or point to a NUL terminated string
+pub(crate) unsafe fn string_arg(s: *const c_char) -> Result<String, Error> {
+    if s.is_null() {
+        return Err(Error::NullPointer);
+    }
+    Ok(unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned())
+}
+
+/// Borrows a handle argument
+///
+/// # Safety
+/// The pointer must be null or point to a live value
+pub(crate) unsafe fn ref_arg<'a, T>(p: *const T) -> Result<&'a T, Error> {
+    unsafe { p.as_ref() }.ok_or(Error::NullPointer)
+}
+
+/// Same as ref_arg but mutable
+///
+/// # Safety
+/// The pointer must be null or point to a live value that is not used anywhere else
+pub(crate) unsafe fn mut_arg<'a, T>(p: *mut T) -> Result<&'a mut T, Error> {
+    unsafe { p.as_mut() }.ok_or(Error::NullPointer)
+}
+
+/// Moves a string into a C string that can be freed with yamlate_string_free,
+/// strings containing a NUL cannot be returned to C
+pub(crate) fn c_string(s: impl Into<Vec<u8>>) -> Result<*const c_char, Error> {
+    CString::new(s)
+        .map(|s| s.into_raw() as *const c_char)
+        .map_err(|_| Error::InvalidString)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard() {
+        let ok: FFIReturnValue<i32> = guard(|| {
+            Ok(FFIReturnValue {
+                value: 1,
+                error: Error::None as i32,
+            })
+        });
+        assert_eq!((ok.value, ok.error), (1, Error::None as i32));
+
+        let null: FFIReturnValue<i32> = guard(|| {
+            let value = unsafe { ref_arg(ptr::null::<i32>()) }?;
+            Ok(FFIReturnValue {
+                value: *value,
+                error: Error::None as i32,
+            })
+        });
+        assert_eq!(null.error, Error::NullPointer as i32);
+
+        let panicked: FFIArrayReturnValue<*const i32> = guard(|| panic!("oops"));
+        assert_eq!(panicked.error, Error::Panic as i32);
+        assert!(panicked.value.is_null());
+
+        assert_eq!(
+            guard(|| c_string("a\0b").map(|_| 0).map_err(LastError::from)),
+            Error::InvalidString as i32
+        );
+    }
+}
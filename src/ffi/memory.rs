@@ -0,0 +1,114 @@
+use crate::ffi::guard::{c_string, guard};
+use crate::ffi::types::Error;
+use libc::c_char;
+use std::ffi::CString;
+use std::ptr;
+
+/// Moves strings into an array that can be freed with yamlate_string_array_free,
+/// fails with InvalidString (freeing the strings moved so far) if a string contains a NUL
+pub(crate) fn string_array(
+    strings: impl IntoIterator<Item = String>,
+) -> Result<(*const *const c_char, i32), Error> {
+    let mut array = Vec::new();
+    for s in strings {
+        match c_string(s) {
+            Ok(s) => array.push(s),
+            Err(error) => {
+                for s in array {
+                    drop(unsafe { CString::from_raw(s as *mut c_char) });
+                }
+                return Err(error);
+            }
+        }
+    }
+    let array = array.into_boxed_slice();
+    let length = array.len() as i32;
+
+    Ok((Box::into_raw(array) as *const *const c_char, length))
+}
+
+/// Frees a string returned by the library (like the value of environment_get_string
+/// or yaml_string_get), does nothing if the string is null
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yamlate_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        guard(|| {
+            drop(unsafe { CString::from_raw(s) });
+            Ok(())
+        })
+    }
+}
+
+/// Frees an array of strings returned by the library (like the value of
+/// environment_keys or yaml_hash_keys) and every string in it,
+/// does nothing if the array is null
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yamlate_string_array_free(array: *mut *const c_char, length: i32) {
+    if array.is_null() || length < 0 {
+        return;
+    }
+
+    guard(|| {
+        let slice = ptr::slice_from_raw_parts_mut(array, length as usize);
+        for &s in unsafe { Box::from_raw(slice) }.iter() {
+            unsafe { yamlate_string_free(s as *mut c_char) };
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::environment::{
+        environment_create, environment_destroy, environment_get_string, environment_keys,
+        environment_set_string, environment_to_json, environment_to_yaml,
+    };
+    use crate::ffi::yaml::{
+        yaml_create_from_string, yaml_destroy, yaml_hash_keys, yaml_string_get,
+    };
+    use std::ffi::CStr;
+    use yaml_rust::yaml::Yaml;
+
+    // run with a leak checker to make sure every string the library returns
+    // can be freed (see the leak check instructions in the README)
+    #[test]
+    fn test_free_returned_strings() {
+        unsafe {
+            let env = environment_create();
+            environment_set_string(env, c"season".as_ptr(), c"spring".as_ptr());
+
+            let season = environment_get_string(env, c"season".as_ptr());
+            assert_eq!(CStr::from_ptr(season.value).to_str(), Ok("spring"));
+            yamlate_string_free(season.value as *mut c_char);
+
+            // strings returned with an error are freed the same way
+            let missing = environment_get_string(env, c"missing".as_ptr());
+            yamlate_string_free(missing.value as *mut c_char);
+
+            let keys = environment_keys(env);
+            assert_eq!(keys.length, 1);
+            yamlate_string_array_free(keys.value as *mut *const c_char, keys.length);
+
+            yamlate_string_free(environment_to_yaml(env) as *mut c_char);
+            yamlate_string_free(environment_to_json(env) as *mut c_char);
+            environment_destroy(env);
+
+            let doc = yaml_create_from_string(c"a: b\nc: d\n".as_ptr()).value;
+            let keys = yaml_hash_keys(doc);
+            assert_eq!(keys.length, 2);
+            yamlate_string_array_free(keys.value as *mut *const c_char, keys.length);
+
+            let wrong_type = yaml_string_get(doc);
+            yamlate_string_free(wrong_type.value as *mut c_char);
+            yaml_destroy(doc as *mut Yaml);
+
+            yamlate_string_free(ptr::null_mut());
+            yamlate_string_array_free(ptr::null_mut(), 0);
+        }
+    }
+}
@@ -1,4 +1,36 @@
+//! C API for embedding the interpreter
+//!
+//! Threading rules:
+//!
+//! * Handles (environments and YAML values) are not synchronized. A handle can
+//!   be used from any thread but not from two threads at the same time.
+//! * Functions that take a `const` YAML pointer only read from it, so a YAML value
+//!   (like a parsed document) can be read and evaluated from many threads at once
+//!   as long as no thread destroys it while it is in use.
+//! * Functions that take an environment pointer can modify it (yaml_evaluate
+//!   included), so each thread evaluating at the same time needs its own
+//!   environment (environment_clone can copy a shared starting state).
+//! * Handles returned by a function are owned by the caller and can be
+//!   passed to and destroyed on another thread.
+//! * Node handles into the same document (from the yaml_node functions) can be
+//!   used and destroyed from different threads at the same time, the document
+//!   is freed by whichever thread destroys the last handle.
+//!
+//! No function panics across the boundary: null pointers are reported with the
+//! NullPointer error (destroy and free functions ignore them), documents that
+//! fail to parse with ParseError, arguments out of their range (like a negative
+//! length) with InvalidArgument and a panic inside the library with Panic.
+//! Functions that only return a handle or a string return null instead.
+//!
+//! When a function fails it also records a description of the error for the
+//! calling thread that can be read with yamlate_last_error_message (and
+//! yamlate_last_error_code and yamlate_last_error_path).
+
+mod error;
+mod guard;
 mod types;
 
 pub mod environment;
+pub mod memory;
+pub mod node;
 pub mod yaml;
@@ -0,0 +1,354 @@
+use crate::environment::ASTEnvironment;
+use crate::ffi::guard::{guard, mut_arg, ref_arg, string_arg};
+use crate::ffi::types::{Error, FFIArrayReturnValue, FFINodeEntry, FFIReturnValue};
+use crate::ffi::yaml::{
+    hash_entry, index_out_of_bounds, invalid_path, key_not_found, load_document, path_not_found,
+    yaml_result,
+};
+use crate::path::get_path;
+use crate::yaml::evaluate;
+use libc::c_char;
+use std::ptr;
+use std::sync::Arc;
+use yaml_rust::yaml::Yaml;
+
+/// A handle to a value inside a YAML document
+///
+/// Every handle into a document shares the document's root through a reference
+/// count, so getting a child handle does not copy anything and the document is
+/// only freed when the last handle into it is destroyed
+pub struct YamlNode {
+    root: Arc<Yaml>,
+    node: *const Yaml,
+}
+
+// the node points into the root which is never modified and
+// is kept alive by the handle, so handles can be sent and shared
+// between threads like an Arc<Yaml>
+unsafe impl Send for YamlNode {}
+unsafe impl Sync for YamlNode {}
+
+impl YamlNode {
+    fn new(root: Yaml) -> YamlNode {
+        let root = Arc::new(root);
+        let node = Arc::as_ptr(&root);
+        YamlNode { root, node }
+    }
+
+    pub fn yaml(&self) -> &Yaml {
+        // the root is alive as long as the handle is
+        unsafe { &*self.node }
+    }
+
+    fn child(&self, child: &Yaml) -> YamlNode {
+        YamlNode {
+            root: self.root.clone(),
+            node: child,
+        }
+    }
+}
+
+fn node_result(node: YamlNode) -> FFIReturnValue<*const YamlNode> {
+    FFIReturnValue {
+        value: Box::into_raw(Box::new(node)),
+        error: Error::None as i32,
+    }
+}
+
+/// Parses a document and returns a handle to its root
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_create_from_string(
+    s: *const c_char,
+) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let yaml_str = unsafe { string_arg(s) }?;
+        let doc = load_document(yaml_str.as_str())?;
+
+        Ok(node_result(YamlNode::new(doc)))
+    })
+}
+
+/// Copies a YAML value (like one returned by yaml_create_from_string
+/// or yaml_evaluate) into a new document and returns a handle to its root
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_from_yaml(yaml: *const Yaml) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+
+        Ok(node_result(YamlNode::new(yaml.clone())))
+    })
+}
+
+/// Returns the value of a handle, it can be passed to any function that takes a
+/// const YAML pointer (yaml_type, yaml_string_get, yaml_evaluate, etc.) but must
+/// not be destroyed and is only valid while the handle is alive
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_yaml(node: *const YamlNode) -> *const Yaml {
+    guard(|| Ok(unsafe { ref_arg(node) }?.node))
+}
+
+/// Returns a handle to a value of a hash without copying it
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_hash_get(
+    node: *const YamlNode,
+    key: *const c_char,
+) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+        let hash_key = unsafe { string_arg(key) }?;
+
+        match *node.yaml() {
+            Yaml::Hash(ref h) => {
+                let child = h
+                    .get(&Yaml::String(hash_key.clone()))
+                    .ok_or_else(|| key_not_found(&hash_key))?;
+                Ok(node_result(node.child(child)))
+            }
+            _ => Err(Error::WrongType.into()),
+        }
+    })
+}
+
+/// Returns a handle to the key of the entry of a hash at an index
+/// (any YAML value, not only strings) without copying it
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_hash_key_at(
+    node: *const YamlNode,
+    index: i32,
+) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+        let (key, _) = hash_entry(node.yaml(), index)?;
+        Ok(node_result(node.child(key)))
+    })
+}
+
+/// Returns a handle to the value of the entry of a hash at an index without copying it
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_hash_value_at(
+    node: *const YamlNode,
+    index: i32,
+) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+        let (_, value) = hash_entry(node.yaml(), index)?;
+        Ok(node_result(node.child(value)))
+    })
+}
+
+/// Returns handles to the key and the value of every entry of a hash in the order
+/// of the document, the entries and their handles are destroyed with
+/// yaml_node_entries_destroy
+///
+/// Walking a hash with yaml_node_hash_key_at and yaml_node_hash_value_at
+/// looks up every index from the start of the hash, this takes one pass
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_hash_entries(
+    node: *const YamlNode,
+) -> FFIArrayReturnValue<*const FFINodeEntry> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+
+        match *node.yaml() {
+            Yaml::Hash(ref h) => {
+                let entries = h
+                    .iter()
+                    .map(|(key, value)| FFINodeEntry {
+                        key: Box::into_raw(Box::new(node.child(key))),
+                        value: Box::into_raw(Box::new(node.child(value))),
+                    })
+                    .collect::<Box<[_]>>();
+                let length = entries.len();
+
+                Ok(FFIArrayReturnValue {
+                    value: Box::into_raw(entries) as *const FFINodeEntry,
+                    length: length as i32,
+                    error: Error::None as i32,
+                })
+            }
+            _ => Err(Error::WrongType.into()),
+        }
+    })
+}
+
+/// Destroys the entries returned by yaml_node_hash_entries and their handles
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_entries_destroy(entries: *mut FFINodeEntry, length: i32) {
+    if entries.is_null() || length < 0 {
+        return;
+    }
+
+    guard(|| {
+        let slice = ptr::slice_from_raw_parts_mut(entries, length as usize);
+        for entry in unsafe { Box::from_raw(slice) }.iter() {
+            unsafe {
+                yaml_node_destroy(entry.key);
+                yaml_node_destroy(entry.value);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Returns a handle to an element of an array without copying it
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_array_get(
+    node: *const YamlNode,
+    index: i32,
+) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+
+        match *node.yaml() {
+            Yaml::Array(ref a) => {
+                let child = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| a.get(index))
+                    .ok_or_else(|| index_out_of_bounds(index))?;
+                Ok(node_result(node.child(child)))
+            }
+            _ => Err(Error::WrongType.into()),
+        }
+    })
+}
+
+/// Returns a handle to the value at a path ("cricket.wing_color", "bugs[3].name")
+/// without copying it, fails with InvalidArgument if the path cannot be parsed
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_get_path(
+    node: *const YamlNode,
+    path: *const c_char,
+) -> FFIReturnValue<*const YamlNode> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+        let path = unsafe { string_arg(path) }?;
+
+        let child = get_path(node.yaml(), path.as_str())
+            .map_err(|err| invalid_path(&path, err))?
+            .ok_or_else(|| path_not_found(&path))?;
+        Ok(node_result(node.child(child)))
+    })
+}
+
+/// Evaluates the value of a handle, the result is a new YAML
+/// value that must be destroyed with yaml_destroy
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_evaluate(
+    node: *const YamlNode,
+    env: *mut ASTEnvironment,
+) -> FFIReturnValue<*const Yaml> {
+    guard(|| {
+        let node = unsafe { ref_arg(node) }?;
+        let environment = unsafe { mut_arg(env) }?;
+
+        yaml_result(evaluate(node.yaml(), environment))
+    })
+}
+
+/// Destroys a handle, the document is freed when its last handle is destroyed,
+/// does nothing if the handle is null
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_node_destroy(node: *const YamlNode) {
+    if !node.is_null() {
+        guard(|| {
+            drop(unsafe { Box::from_raw(node as *mut YamlNode) });
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::memory::yamlate_string_free;
+    use crate::ffi::yaml::yaml_string_get;
+    use std::ffi::{CStr, CString};
+
+    #[test]
+    fn test_children_outlive_root_handle() {
+        let s = CString::new("bugs:\n  - name: beetle\n  - name: ant\n").unwrap();
+
+        unsafe {
+            let root = yaml_node_create_from_string(s.as_ptr()).value;
+            let bugs = yaml_node_hash_get(root, c"bugs".as_ptr()).value;
+            let ant = yaml_node_array_get(bugs, 1).value;
+            let name = yaml_node_get_path(ant, c"name".as_ptr()).value;
+
+            // children point into the root document instead of copying it
+            assert_eq!(Arc::strong_count(&(*root).root), 4);
+            assert!(ptr::eq(
+                yaml_node_yaml(bugs),
+                &(*root).yaml()["bugs"] as *const Yaml
+            ));
+
+            yaml_node_destroy(root);
+            yaml_node_destroy(bugs);
+            yaml_node_destroy(ant);
+
+            // the document is kept alive by the remaining handle
+            let value = yaml_string_get(yaml_node_yaml(name)).value;
+            assert_eq!(CStr::from_ptr(value).to_str(), Ok("ant"));
+            yamlate_string_free(value as *mut c_char);
+            assert_eq!(Arc::strong_count(&(*name).root), 1);
+            yaml_node_destroy(name);
+
+            let missing = yaml_node_array_get(ptr::null(), 0);
+            assert_eq!(missing.error, Error::NullPointer as i32);
+        }
+    }
+
+    #[test]
+    fn test_hash_entries() {
+        let s = CString::new("cricket: red\n3: [1, 2]\nbeetle: blue\n").unwrap();
+
+        unsafe {
+            let root = yaml_node_create_from_string(s.as_ptr()).value;
+            let entries = yaml_node_hash_entries(root);
+            assert_eq!(entries.error, Error::None as i32);
+            assert_eq!(entries.length, 3);
+
+            let slice = std::slice::from_raw_parts(entries.value, entries.length as usize);
+            let hash = (*root).yaml().as_hash().unwrap();
+            for (entry, (key, value)) in slice.iter().zip(hash.iter()) {
+                // the handles point into the document in the order of the document
+                assert!(ptr::eq(yaml_node_yaml(entry.key), key));
+                assert!(ptr::eq(yaml_node_yaml(entry.value), value));
+            }
+            assert_eq!(Arc::strong_count(&(*root).root), 7);
+
+            yaml_node_entries_destroy(entries.value as *mut FFINodeEntry, entries.length);
+            assert_eq!(Arc::strong_count(&(*root).root), 1);
+
+            let cricket = yaml_node_hash_get(root, c"cricket".as_ptr()).value;
+            let not_hash = yaml_node_hash_entries(cricket);
+            assert_eq!(not_hash.error, Error::WrongType as i32);
+            assert!(not_hash.value.is_null());
+            yaml_node_destroy(cricket);
+            yaml_node_destroy(root);
+        }
+    }
+}
@@ -1,11 +1,38 @@
+use crate::ffi::node::YamlNode;
+use libc::c_char;
+use yaml_rust::yaml::Yaml;
+
 /// Represents an error value returned
 /// from a FFI function
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
 pub enum Error {
     None = 0,
     WrongType = -1,
     NotDefined = -2,
     InvalidString = -3,
     EvalError = -4,
+    NullPointer = -5,
+    ParseError = -6,
+    Panic = -7,
+    InvalidArgument = -8,
+}
+
+impl Error {
+    pub fn description(&self) -> &str {
+        match *self {
+            Error::None => "No error",
+            Error::WrongType => "Value has the wrong type",
+            Error::NotDefined => "Value is not defined",
+            Error::InvalidString => "String is not valid",
+            Error::EvalError => "Error evaluating expression",
+            Error::NullPointer => "Null pointer passed as an argument",
+            Error::ParseError => "Error parsing YAML",
+            Error::Panic => "Internal error",
+            Error::InvalidArgument => "Invalid argument",
+        }
+    }
 }
 
 /// Represents the return value of a FFI function
@@ -23,7 +50,26 @@ pub struct FFIArrayReturnValue<T> {
     pub error: i32,
 }
 
+/// Represents a change to a variable of an environment for FFI
+/// old_value and expression are null when the variable was
+/// not declared before or the change was not made by an expression
+#[repr(C)]
+pub struct FFIChange {
+    pub name: *const c_char,
+    pub old_value: *const Yaml,
+    pub new_value: *const Yaml,
+    pub expression: *const c_char,
+}
+
+/// Represents an entry of a hash for FFI as handles to its key and its value
+#[repr(C)]
+pub struct FFINodeEntry {
+    pub key: *const YamlNode,
+    pub value: *const YamlNode,
+}
+
 /// Represents a YAML type for FFI
+#[repr(C)]
 pub enum YamlType {
     Integer,
     Real,
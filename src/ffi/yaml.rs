@@ -1,40 +1,101 @@
+use crate::ast::Lit;
 use crate::environment::ASTEnvironment;
+use crate::errors::{EvalError, PathError, YamlError};
+use crate::ffi::error::{LastError, set_last_error};
+use crate::ffi::guard::{c_string, guard, mut_arg, ref_arg, string_arg};
+use crate::ffi::memory::string_array;
 use crate::ffi::types::{Error, FFIArrayReturnValue, FFIReturnValue, YamlType};
-use crate::yaml::evaluate;
+use crate::json::to_json;
+use crate::path::get_path;
+use crate::vm::Vm;
+use crate::yaml::{evaluate, evaluate_path, evaluate_path_with};
 use libc::c_char;
-use std::ffi::{CStr, CString};
-use std::ptr;
+use linked_hash_map::LinkedHashMap;
+use std::{ptr, slice};
 use yaml_rust::YamlLoader;
 use yaml_rust::yaml::Yaml;
 
+/// Moves a result into a YAML return value, the value of a
+/// path that is not found is reported as not defined
+pub(crate) fn yaml_result(
+    result: Result<Yaml, YamlError>,
+) -> Result<FFIReturnValue<*const Yaml>, LastError> {
+    match result {
+        Ok(result) => Ok(FFIReturnValue {
+            value: Box::into_raw(Box::new(result)),
+            error: Error::None as i32,
+        }),
+        Err(error @ YamlError::EvalError(EvalError::PathNotFound(_))) => {
+            Err(LastError::new(Error::NotDefined, error.description()))
+        }
+        Err(error @ YamlError::PathError(_)) => {
+            Err(LastError::new(Error::InvalidArgument, error.description()))
+        }
+        Err(error) => Err(LastError::new(Error::EvalError, error.description())),
+    }
+}
+
+/// Reports a path that is not found in a document
+pub(crate) fn path_not_found(path: &str) -> LastError {
+    LastError::new(
+        Error::NotDefined,
+        EvalError::PathNotFound(path.to_owned()).description(),
+    )
+    .with_path(path)
+}
+
+/// Reports a path that cannot be parsed
+pub(crate) fn invalid_path(path: &str, error: PathError) -> LastError {
+    LastError::new(Error::InvalidArgument, error.description()).with_path(path)
+}
+
+pub(crate) fn key_not_found(key: &str) -> LastError {
+    LastError::new(Error::NotDefined, format!("Key {:?} not in hash", key))
+}
+
+pub(crate) fn index_out_of_bounds(index: i32) -> LastError {
+    LastError::new(Error::NotDefined, format!("Index {} not in array", index))
+}
+
+/// Parses the last document of a string, fails with ParseError
+/// if it is not valid YAML and InvalidString if it is empty
+pub(crate) fn load_document(s: &str) -> Result<Yaml, LastError> {
+    YamlLoader::load_from_str(s)
+        .map_err(|error| {
+            LastError::new(
+                Error::ParseError,
+                format!("{}: {}", Error::ParseError.description(), error),
+            )
+        })?
+        .pop()
+        .ok_or_else(|| LastError::new(Error::InvalidString, "String has no YAML document"))
+}
+
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_create_from_string(s: *const c_char) -> FFIReturnValue<*const Yaml> {
-    let yaml_str: String = unsafe { CStr::from_ptr(s).to_string_lossy().into_owned() };
-    let mut docs = YamlLoader::load_from_str(yaml_str.as_str()).unwrap();
-
-    let doc_option = docs.pop();
+    guard(|| {
+        let yaml_str = unsafe { string_arg(s) }?;
+        let doc = load_document(yaml_str.as_str())?;
 
-    if let Some(doc) = doc_option {
-        let yaml_ptr = Box::into_raw(Box::new(doc));
-
-        FFIReturnValue {
-            value: yaml_ptr,
+        Ok(FFIReturnValue {
+            value: Box::into_raw(Box::new(doc)) as *const Yaml,
             error: Error::None as i32,
-        }
-    } else {
-        FFIReturnValue {
-            value: ptr::null(),
-            error: Error::InvalidString as i32,
-        }
-    }
+        })
+    })
 }
 
+/// Does nothing if the YAML value is null
+///
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_destroy(yaml: *mut Yaml) {
-    assert!(!yaml.is_null());
-    drop(unsafe { Box::from_raw(yaml) })
+    if !yaml.is_null() {
+        guard(|| {
+            drop(unsafe { Box::from_raw(yaml) });
+            Ok(())
+        })
+    }
 }
 
 /// # Safety
@@ -43,187 +104,802 @@ pub unsafe extern "C" fn yaml_evaluate(
     yaml: *const Yaml,
     env: *mut ASTEnvironment,
 ) -> FFIReturnValue<*const Yaml> {
-    if let (Some(yaml), Some(environment)) = unsafe { (yaml.as_ref(), env.as_mut()) }
-        && let Ok(result) = evaluate(yaml, environment)
-    {
-        return FFIReturnValue {
-            value: Box::into_raw(Box::new(result)),
-            error: Error::None as i32,
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+        let environment = unsafe { mut_arg(env) }?;
+
+        yaml_result(evaluate(yaml, environment))
+    })
+}
+
+/// Returns the value at a path of the document ("cricket.wing_color", "bugs[3].name"),
+/// fails with NotDefined if it is missing and InvalidArgument if the path cannot be parsed
+///
+/// The value is borrowed from the document, not copied: it must not be
+/// destroyed and it can only be used while the document is alive
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_get_path(
+    yaml: *const Yaml,
+    path: *const c_char,
+) -> FFIReturnValue<*const Yaml> {
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+        let path = unsafe { string_arg(path) }?;
+
+        match get_path(yaml, path.as_str()).map_err(|err| invalid_path(&path, err))? {
+            Some(value) => Ok(FFIReturnValue {
+                value: value as *const Yaml,
+                error: Error::None as i32,
+            }),
+            None => Err(path_not_found(&path)),
+        }
+    })
+}
+
+/// Evaluates the value at a path of the document without copying
+/// the parts of the document leading to it
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_evaluate_path(
+    yaml: *const Yaml,
+    path: *const c_char,
+    env: *mut ASTEnvironment,
+) -> FFIReturnValue<*const Yaml> {
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+        let path = unsafe { string_arg(path) }?;
+        let environment = unsafe { mut_arg(env) }?;
+
+        yaml_result(evaluate_path(yaml, path.as_str(), environment))
+            .map_err(|error| error.with_path(&path))
+    })
+}
+
+/// Evaluates the value at paths[i] of the document in envs[i] for every i
+/// returning the results in one array (the value of an item is null and its
+/// error is set when its path is not found or evaluating it fails)
+/// the array must be freed with yaml_batch_destroy
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_evaluate_batch(
+    yaml: *const Yaml,
+    paths: *const *const c_char,
+    envs: *const *mut ASTEnvironment,
+    length: i32,
+) -> FFIArrayReturnValue<*const FFIReturnValue<*const Yaml>> {
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+        unsafe { ref_arg(paths) }?;
+        unsafe { ref_arg(envs) }?;
+        if length < 0 {
+            return Err(LastError::new(
+                Error::InvalidArgument,
+                "Batch length is negative",
+            ));
+        }
+
+        let (paths, envs) = unsafe {
+            (
+                slice::from_raw_parts(paths, length as usize),
+                slice::from_raw_parts(envs, length as usize),
+            )
         };
-    }
+        let paths = paths
+            .iter()
+            .map(|&path| unsafe { string_arg(path) })
+            .collect::<Result<Vec<_>, _>>()?;
+        if envs.iter().any(|env| env.is_null()) {
+            return Err(Error::NullPointer.into());
+        }
+
+        // an environment is only borrowed while its own item is evaluated
+        // so the same environment can be passed for several items
+        // (the last error is the error of the last item that failed)
+        let mut vm = Vm::new();
+        let results = paths
+            .iter()
+            .zip(envs)
+            .map(|(path, &env)| {
+                let environment = unsafe { &mut *env };
+                let result = evaluate_path_with(yaml, path, environment, &mut vm);
+                yaml_result(result).unwrap_or_else(|error| FFIReturnValue {
+                    value: ptr::null(),
+                    error: set_last_error(error.with_path(path)),
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        Ok(FFIArrayReturnValue {
+            value: Box::into_raw(results) as *const FFIReturnValue<*const Yaml>,
+            length,
+            error: Error::None as i32,
+        })
+    })
+}
 
-    FFIReturnValue {
-        value: std::ptr::null::<Yaml>(),
-        error: Error::EvalError as i32,
+/// Frees the results returned by yaml_evaluate_batch and the YAML values in them
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_batch_destroy(
+    results: *mut FFIReturnValue<*const Yaml>,
+    length: i32,
+) {
+    if results.is_null() || length < 0 {
+        return;
     }
+
+    guard(|| {
+        let slice = ptr::slice_from_raw_parts_mut(results, length as usize);
+        for result in unsafe { Box::from_raw(slice) }.iter() {
+            if !result.value.is_null() {
+                drop(unsafe { Box::from_raw(result.value as *mut Yaml) });
+            }
+        }
+        Ok(())
+    })
 }
 
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_type(yaml: *const Yaml) -> i32 {
-    match unsafe { yaml.as_ref() } {
-        Some(Yaml::Real(_)) => YamlType::Real as i32,
-        Some(Yaml::Integer(_)) => YamlType::Integer as i32,
-        Some(Yaml::String(_)) => YamlType::String as i32,
-        Some(Yaml::Boolean(_)) => YamlType::Boolean as i32,
-        Some(Yaml::Array(_)) => YamlType::Array as i32,
-        Some(Yaml::Hash(_)) => YamlType::Hash as i32,
-        _ => YamlType::Null as i32,
-    }
+    guard(|| {
+        Ok(match unsafe { yaml.as_ref() } {
+            Some(Yaml::Real(_)) => YamlType::Real as i32,
+            Some(Yaml::Integer(_)) => YamlType::Integer as i32,
+            Some(Yaml::String(_)) => YamlType::String as i32,
+            Some(Yaml::Boolean(_)) => YamlType::Boolean as i32,
+            Some(Yaml::Array(_)) => YamlType::Array as i32,
+            Some(Yaml::Hash(_)) => YamlType::Hash as i32,
+            _ => YamlType::Null as i32,
+        })
+    })
 }
 
+/// Fails with WrongType if the integer does not fit in 32 bits,
+/// yaml_integer64_get returns every integer
+///
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_integer_get(yaml: *const Yaml) -> FFIReturnValue<i32> {
-    if let Some(Yaml::Integer(i)) = unsafe { yaml.as_ref() } {
-        FFIReturnValue {
-            value: *i as i32,
-            error: Error::None as i32,
-        }
-    } else {
-        FFIReturnValue {
-            value: 0,
-            error: Error::WrongType as i32,
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Integer(i) => {
+            let value = i32::try_from(i).map_err(|_| {
+                LastError::new(
+                    Error::WrongType,
+                    format!("Integer {} does not fit in 32 bits", i),
+                )
+            })?;
+
+            Ok(FFIReturnValue {
+                value,
+                error: Error::None as i32,
+            })
         }
-    }
+        _ => Err(Error::WrongType.into()),
+    })
+}
+
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_integer64_get(yaml: *const Yaml) -> FFIReturnValue<i64> {
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Integer(i) => Ok(FFIReturnValue {
+            value: i,
+            error: Error::None as i32,
+        }),
+        _ => Err(Error::WrongType.into()),
+    })
 }
 
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_decimal_get(yaml: *const Yaml) -> FFIReturnValue<f64> {
-    if let Some(Yaml::Real(s)) = unsafe { yaml.as_ref() } {
-        FFIReturnValue {
-            value: s.parse::<f64>().unwrap_or(0.0),
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        ref yaml @ Yaml::Real(_) => Ok(FFIReturnValue {
+            value: yaml.as_f64().unwrap_or(0.0),
             error: Error::None as i32,
-        }
-    } else {
-        FFIReturnValue {
-            value: 0.0,
-            error: Error::WrongType as i32,
-        }
-    }
+        }),
+        _ => Err(Error::WrongType.into()),
+    })
 }
 
 /// # Safety
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn yaml_string_get(yaml: *const Yaml) -> FFIReturnValue<*const c_char> {
-    if let Some(Yaml::String(s)) = unsafe { yaml.as_ref() } {
-        let c_str = CString::new(s.as_str()).unwrap().into_raw();
+pub unsafe extern "C" fn yaml_boolean_get(yaml: *const Yaml) -> FFIReturnValue<bool> {
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Boolean(b) => Ok(FFIReturnValue {
+            value: b,
+            error: Error::None as i32,
+        }),
+        _ => Err(Error::WrongType.into()),
+    })
+}
 
-        FFIReturnValue {
-            value: c_str as *const c_char,
+/// Returns true if the value is null (a missing value like the
+/// result of a statement that returns nothing is null too)
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_is_null(yaml: *const Yaml) -> FFIReturnValue<bool> {
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+        Ok(FFIReturnValue {
+            value: matches!(*yaml, Yaml::Null | Yaml::BadValue),
             error: Error::None as i32,
-        }
-    } else {
-        FFIReturnValue {
-            value: CString::new("").unwrap().into_raw() as *const c_char,
-            error: Error::WrongType as i32,
-        }
-    }
+        })
+    })
 }
 
+/// The returned string (an empty string on error, null if the YAML value is null)
+/// must be freed with yamlate_string_free
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_string_get(yaml: *const Yaml) -> FFIReturnValue<*const c_char> {
+    guard(|| {
+        let (value, error) = match *unsafe { ref_arg(yaml) }? {
+            Yaml::String(ref s) => (s.as_str(), Error::None),
+            _ => ("", Error::WrongType),
+        };
+
+        Ok(FFIReturnValue {
+            value: c_string(value)?,
+            error: error as i32,
+        })
+    })
+}
+
+/// Returns a value (like the result of an evaluation) as JSON so it can be decoded
+/// in one call, the string must be freed with yamlate_string_free
+///
+/// Hash keys that are not strings are written as the JSON of the key inside a string
+/// and reals that are not finite are written as null
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_to_json(yaml: *const Yaml) -> *const c_char {
+    guard(|| Ok(c_string(to_json(unsafe { ref_arg(yaml) }?))?))
+}
+
+/// Returns the string keys of a hash, the array
+/// must be freed with yamlate_string_array_free
+///
+/// Keys that are not strings are left out, yaml_hash_key_at
+/// returns every key
+///
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_hash_keys(
     yaml: *const Yaml,
 ) -> FFIArrayReturnValue<*const *const c_char> {
-    let mut keys: Vec<*const c_char> = Vec::new();
-
-    if let Some(Yaml::Hash(h)) = unsafe { yaml.as_ref() } {
-        for (key, _) in h {
-            if let Yaml::String(ref s) = *key {
-                let c_str = CString::new(s.as_str()).unwrap().into_raw();
-                keys.push(c_str as *const c_char);
-            }
-        }
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Hash(ref h) => {
+            let keys = h.keys().filter_map(|key| key.as_str().map(str::to_owned));
+            let (value, length) = string_array(keys)?;
 
-        keys.shrink_to_fit();
-        let length = keys.len();
-
-        FFIArrayReturnValue {
-            value: keys.leak().as_ptr(),
-            length: length as i32,
-            error: Error::None as i32,
-        }
-    } else {
-        FFIArrayReturnValue {
-            value: ptr::null(),
-            length: 0,
-            error: Error::WrongType as i32,
+            Ok(FFIArrayReturnValue {
+                value,
+                length,
+                error: Error::None as i32,
+            })
         }
-    }
+        _ => Err(Error::WrongType.into()),
+    })
 }
 
+/// Returns a copy of a value of a hash that must be destroyed with yaml_destroy
+///
+/// Deprecated: the value and everything in it is copied on every call.
+/// yaml_node_hash_get returns a handle to the value without copying it
+///
 /// # Safety
+#[deprecated(note = "copies the value, use yaml_node_hash_get")]
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_hash_get(
     yaml: *const Yaml,
     key: *const c_char,
 ) -> FFIReturnValue<*const Yaml> {
-    let hash_key: String = unsafe { CStr::from_ptr(key).to_string_lossy().into_owned() };
+    guard(|| {
+        let yaml = unsafe { ref_arg(yaml) }?;
+        let hash_key = unsafe { string_arg(key) }?;
 
-    if let Some(Yaml::Hash(h)) = unsafe { yaml.as_ref() } {
-        if let Some(result) = h.get(&Yaml::String(hash_key)) {
-            let yaml_ptr = Box::into_raw(Box::new(result.clone()));
+        match *yaml {
+            Yaml::Hash(ref h) => {
+                let result = h
+                    .get(&Yaml::String(hash_key.clone()))
+                    .ok_or_else(|| key_not_found(&hash_key))?;
 
-            FFIReturnValue {
-                value: yaml_ptr,
-                error: Error::None as i32,
-            }
-        } else {
-            FFIReturnValue {
-                value: ptr::null(),
-                error: Error::NotDefined as i32,
+                Ok(FFIReturnValue {
+                    value: Box::into_raw(Box::new(result.clone())) as *const Yaml,
+                    error: Error::None as i32,
+                })
             }
+            _ => Err(Error::WrongType.into()),
         }
-    } else {
-        FFIReturnValue {
-            value: ptr::null(),
-            error: Error::WrongType as i32,
-        }
+    })
+}
+
+/// Returns the key and the value of the entry of a hash at an index
+/// (entries are in the order of the document)
+pub(crate) fn hash_entry(yaml: &Yaml, index: i32) -> Result<(&Yaml, &Yaml), LastError> {
+    match *yaml {
+        Yaml::Hash(ref h) => usize::try_from(index)
+            .ok()
+            .and_then(|index| h.iter().nth(index))
+            .ok_or_else(|| index_out_of_bounds(index)),
+        _ => Err(Error::WrongType.into()),
     }
 }
 
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_hash_len(yaml: *const Yaml) -> FFIReturnValue<i32> {
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Hash(ref h) => Ok(FFIReturnValue {
+            value: h.len() as i32,
+            error: Error::None as i32,
+        }),
+        _ => Err(Error::WrongType.into()),
+    })
+}
+
+/// Returns a copy of the key of the entry of a hash at an index (any YAML value,
+/// not only strings) that must be destroyed with yaml_destroy
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_hash_key_at(
+    yaml: *const Yaml,
+    index: i32,
+) -> FFIReturnValue<*const Yaml> {
+    guard(|| {
+        let (key, _) = hash_entry(unsafe { ref_arg(yaml) }?, index)?;
+        Ok(FFIReturnValue {
+            value: Box::into_raw(Box::new(key.clone())) as *const Yaml,
+            error: Error::None as i32,
+        })
+    })
+}
+
+/// Returns a copy of the value of the entry of a hash at an index
+/// that must be destroyed with yaml_destroy
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_hash_value_at(
+    yaml: *const Yaml,
+    index: i32,
+) -> FFIReturnValue<*const Yaml> {
+    guard(|| {
+        let (_, value) = hash_entry(unsafe { ref_arg(yaml) }?, index)?;
+        Ok(FFIReturnValue {
+            value: Box::into_raw(Box::new(value.clone())) as *const Yaml,
+            error: Error::None as i32,
+        })
+    })
+}
+
 /// # Safety
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_array_len(yaml: *const Yaml) -> FFIReturnValue<i32> {
-    if let Some(Yaml::Array(a)) = unsafe { yaml.as_ref() } {
-        FFIReturnValue {
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Array(ref a) => Ok(FFIReturnValue {
             value: a.len() as i32,
             error: Error::None as i32,
-        }
-    } else {
-        FFIReturnValue {
-            value: 0,
-            error: Error::WrongType as i32,
-        }
-    }
+        }),
+        _ => Err(Error::WrongType.into()),
+    })
 }
 
+/// Returns a copy of an element of an array that must be destroyed with yaml_destroy
+///
+/// Deprecated: the element and everything in it is copied on every call.
+/// yaml_node_array_get returns a handle to the element without copying it
+///
 /// # Safety
+#[deprecated(note = "copies the element, use yaml_node_array_get")]
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn yaml_array_get(
     yaml: *const Yaml,
     index: i32,
 ) -> FFIReturnValue<*const Yaml> {
-    if let Some(Yaml::Array(a)) = unsafe { yaml.as_ref() } {
-        if let Some(result) = a.get(index as usize) {
-            let yaml_ptr = Box::into_raw(Box::new(result.clone()));
+    guard(|| match *unsafe { ref_arg(yaml) }? {
+        Yaml::Array(ref a) => {
+            let result = usize::try_from(index)
+                .ok()
+                .and_then(|index| a.get(index))
+                .ok_or_else(|| index_out_of_bounds(index))?;
 
-            FFIReturnValue {
-                value: yaml_ptr,
+            Ok(FFIReturnValue {
+                value: Box::into_raw(Box::new(result.clone())) as *const Yaml,
                 error: Error::None as i32,
+            })
+        }
+        _ => Err(Error::WrongType.into()),
+    })
+}
+
+/// Creates a null value, the values created by the yaml_*_create functions
+/// must be destroyed with yaml_destroy unless they are moved into an array
+/// or a hash
+#[unsafe(no_mangle)]
+pub extern "C" fn yaml_null_create() -> *mut Yaml {
+    guard(|| Ok(Box::into_raw(Box::new(Yaml::Null))))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn yaml_boolean_create(value: bool) -> *mut Yaml {
+    guard(|| Ok(Box::into_raw(Box::new(Yaml::Boolean(value)))))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn yaml_integer_create(value: i64) -> *mut Yaml {
+    guard(|| Ok(Box::into_raw(Box::new(Yaml::Integer(value)))))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn yaml_decimal_create(value: f64) -> *mut Yaml {
+    guard(|| Ok(Box::into_raw(Box::new(Yaml::from(Lit::Decimal(value))))))
+}
+
+/// Copies a string into a new value, returns null if the string is null
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_string_create(value: *const c_char) -> *mut Yaml {
+    guard(|| {
+        let value = unsafe { string_arg(value) }?;
+        Ok(Box::into_raw(Box::new(Yaml::String(value))))
+    })
+}
+
+/// Creates an empty array
+#[unsafe(no_mangle)]
+pub extern "C" fn yaml_array_create() -> *mut Yaml {
+    guard(|| Ok(Box::into_raw(Box::new(Yaml::Array(Vec::new())))))
+}
+
+/// Creates an empty hash
+#[unsafe(no_mangle)]
+pub extern "C" fn yaml_hash_create() -> *mut Yaml {
+    guard(|| Ok(Box::into_raw(Box::new(Yaml::Hash(LinkedHashMap::new())))))
+}
+
+/// Moves a value to the end of an array, the value must not be used or
+/// destroyed afterwards unless the function fails
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_array_push(array: *mut Yaml, value: *mut Yaml) -> i32 {
+    guard(|| {
+        let array = unsafe { mut_arg(array) }?;
+        unsafe { ref_arg(value) }?;
+
+        match *array {
+            Yaml::Array(ref mut a) => {
+                a.push(*unsafe { Box::from_raw(value) });
+                Ok(Error::None as i32)
+            }
+            _ => Err(Error::WrongType.into()),
+        }
+    })
+}
+
+/// Moves a key and a value into a hash, replacing the value of an equal key
+/// (the key can be any value, not only a string), the key and the value must not
+/// be used or destroyed afterwards unless the function fails
+///
+/// # Safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn yaml_hash_insert(
+    hash: *mut Yaml,
+    key: *mut Yaml,
+    value: *mut Yaml,
+) -> i32 {
+    guard(|| {
+        let hash = unsafe { mut_arg(hash) }?;
+        unsafe { ref_arg(key) }?;
+        unsafe { ref_arg(value) }?;
+
+        match *hash {
+            Yaml::Hash(ref mut h) => {
+                let (key, value) = unsafe { (Box::from_raw(key), Box::from_raw(value)) };
+                h.insert(*key, *value);
+                Ok(Error::None as i32)
             }
-        } else {
-            FFIReturnValue {
-                value: ptr::null(),
-                error: Error::NotDefined as i32,
+            _ => Err(Error::WrongType.into()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::environment::{
+        environment_clone, environment_create, environment_destroy, environment_get_integer,
+        environment_set_integer, environment_set_string,
+    };
+    use crate::ffi::memory::{yamlate_string_array_free, yamlate_string_free};
+    use std::ffi::{CStr, CString};
+    use std::thread;
+
+    // raw pointers are not Send, the threading rules in the module docs are what
+    // make sharing a document and sending an environment between threads safe
+    struct Shared<T>(*mut T);
+    unsafe impl<T> Send for Shared<T> {}
+    unsafe impl<T> Sync for Shared<T> {}
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_evaluate_from_many_threads() {
+        let s = CString::new("foo:\n  - '~> n = n * 2'\n  - '~> n'\n").unwrap();
+        let name = CString::new("n").unwrap();
+        let key = CString::new("foo").unwrap();
+
+        unsafe {
+            let doc = yaml_create_from_string(s.as_ptr());
+            let foo = yaml_hash_get(doc.value, key.as_ptr());
+            let env = environment_create();
+            environment_set_integer(env, name.as_ptr(), 1);
+
+            let (foo, env) = (Shared(foo.value as *mut Yaml), Shared(env));
+            thread::scope(|scope| {
+                for i in 0..8 {
+                    let (foo, env, name) = (&foo, &env, &name);
+                    scope.spawn(move || {
+                        // every thread evaluates the shared document in its own environment
+                        let env = environment_clone(env.0);
+                        environment_set_integer(env, name.as_ptr(), i);
+
+                        let result = yaml_evaluate(foo.0, env);
+                        assert_eq!(result.error, Error::None as i32);
+                        assert_eq!(yaml_integer_get(result.value).value, i * 2);
+
+                        yaml_destroy(result.value as *mut Yaml);
+                        environment_destroy(env);
+                    });
+                }
+            });
+
+            environment_destroy(env.0);
+            yaml_destroy(foo.0);
+            yaml_destroy(doc.value as *mut Yaml);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_batch() {
+        let s = CString::new("foo:\n  bar: '~> n + 1'\n  inc: '~> n = n + 1'\n").unwrap();
+        let name = CString::new("n").unwrap();
+        let paths = [
+            c"foo.bar".as_ptr(),
+            c"foo.baz".as_ptr(),
+            c"foo.bar".as_ptr(),
+        ];
+
+        unsafe {
+            let doc = yaml_create_from_string(s.as_ptr());
+            let envs = [
+                environment_create(),
+                environment_create(),
+                environment_create(),
+            ];
+            environment_set_integer(envs[0], name.as_ptr(), 1);
+            environment_set_integer(envs[1], name.as_ptr(), 2);
+
+            let results = yaml_evaluate_batch(doc.value, paths.as_ptr(), envs.as_ptr(), 3);
+            assert_eq!(results.length, 3);
+
+            let items = slice::from_raw_parts(results.value, 3);
+            assert_eq!(yaml_integer_get(items[0].value).value, 2);
+            assert_eq!(items[1].error, Error::NotDefined as i32);
+            // n is not defined in the third environment
+            assert_eq!(items[2].error, Error::EvalError as i32);
+
+            yaml_batch_destroy(results.value as *mut _, results.length);
+
+            // the same environment can be used for several items
+            let paths = [c"foo.inc".as_ptr(), c"foo.inc".as_ptr()];
+            let same = [envs[0], envs[0]];
+            let results = yaml_evaluate_batch(doc.value, paths.as_ptr(), same.as_ptr(), 2);
+            let items = slice::from_raw_parts(results.value, 2);
+            assert_eq!(yaml_integer_get(items[0].value).value, 2);
+            assert_eq!(yaml_integer_get(items[1].value).value, 3);
+            assert_eq!(environment_get_integer(envs[0], name.as_ptr()).value, 3);
+            yaml_batch_destroy(results.value as *mut _, results.length);
+
+            let results = yaml_evaluate_batch(doc.value, paths.as_ptr(), same.as_ptr(), -1);
+            assert_eq!(results.error, Error::InvalidArgument as i32);
+
+            for env in envs {
+                environment_destroy(env);
             }
+            yaml_destroy(doc.value as *mut Yaml);
         }
-    } else {
-        FFIReturnValue {
-            value: ptr::null(),
-            error: Error::WrongType as i32,
+    }
+
+    #[test]
+    fn test_paths() {
+        let s = CString::new("bugs:\n  - name: beetle\n    legs: '~> n * 2'\n").unwrap();
+        let name = CString::new("n").unwrap();
+
+        unsafe {
+            let doc = yaml_create_from_string(s.as_ptr());
+
+            let bug = yaml_get_path(doc.value, c"bugs[0].name".as_ptr());
+            assert_eq!(bug.error, Error::None as i32);
+            let bug_name = yaml_string_get(bug.value).value;
+            assert_eq!(CStr::from_ptr(bug_name).to_str(), Ok("beetle"));
+            yamlate_string_free(bug_name as *mut c_char);
+            assert!(
+                yaml_get_path(doc.value, c"bugs[1]".as_ptr())
+                    .value
+                    .is_null()
+            );
+
+            let env = environment_create();
+            environment_set_integer(env, name.as_ptr(), 3);
+            let result = yaml_evaluate_path(doc.value, c"bugs[0].legs".as_ptr(), env);
+            assert_eq!(yaml_integer_get(result.value).value, 6);
+
+            // a path that cannot be parsed is an invalid argument, not a missing value
+            let bad = yaml_get_path(doc.value, c"bugs[0".as_ptr());
+            assert_eq!(bad.error, Error::InvalidArgument as i32);
+            assert!(bad.value.is_null());
+            let bad = yaml_evaluate_path(doc.value, c"bugs..legs".as_ptr(), env);
+            assert_eq!(bad.error, Error::InvalidArgument as i32);
+
+            yaml_destroy(result.value as *mut Yaml);
+            environment_destroy(env);
+            yaml_destroy(doc.value as *mut Yaml);
+        }
+    }
+
+    #[test]
+    fn test_bad_input() {
+        unsafe {
+            let doc = yaml_create_from_string(c"foo: [1, 2".as_ptr());
+            assert_eq!(doc.error, Error::ParseError as i32);
+            assert!(doc.value.is_null());
+            let doc = yaml_create_from_string(ptr::null());
+            assert_eq!(doc.error, Error::NullPointer as i32);
+
+            let env = environment_create();
+            assert_eq!(
+                environment_set_integer(ptr::null_mut(), c"n".as_ptr(), 1),
+                Error::NullPointer as i32
+            );
+            assert_eq!(
+                environment_set_integer(env, ptr::null(), 1),
+                Error::NullPointer as i32
+            );
+
+            // strings with a NUL cannot be returned to C
+            let doc = yaml_create_from_string(c"foo: \"a\\0b\"".as_ptr()).value;
+            let foo = yaml_get_path(doc, c"foo".as_ptr()).value;
+            let value = yaml_string_get(foo);
+            assert_eq!(value.error, Error::InvalidString as i32);
+            assert!(value.value.is_null());
+
+            let result = yaml_evaluate(doc, ptr::null_mut());
+            assert_eq!(result.error, Error::NullPointer as i32);
+            let results = yaml_evaluate_batch(doc, ptr::null(), &env, 1);
+            assert_eq!(results.error, Error::NullPointer as i32);
+
+            yaml_destroy(doc as *mut Yaml);
+            yaml_destroy(ptr::null_mut());
+            environment_destroy(env);
+        }
+    }
+
+    #[test]
+    fn test_accessors() {
+        let s = c"big: 8589934592\nshiny: true\nmate: ~\n1: one\n[2, 3]: pair\nspan: .inf\n";
+
+        unsafe {
+            let doc = yaml_create_from_string(s.as_ptr()).value;
+
+            let big = yaml_get_path(doc, c"big".as_ptr()).value;
+            assert_eq!(yaml_integer_get(big).error, Error::WrongType as i32);
+            assert_eq!(yaml_integer64_get(big).value, 8589934592);
+            let shiny = yaml_get_path(doc, c"shiny".as_ptr()).value;
+            assert!(yaml_boolean_get(shiny).value);
+            assert_eq!(yaml_boolean_get(big).error, Error::WrongType as i32);
+            let mate = yaml_get_path(doc, c"mate".as_ptr()).value;
+            assert!(yaml_is_null(mate).value);
+            assert!(!yaml_is_null(shiny).value);
+            assert_eq!(yaml_is_null(ptr::null()).error, Error::NullPointer as i32);
+            let span = yaml_get_path(doc, c"span".as_ptr()).value;
+            assert_eq!(yaml_decimal_get(span).value, f64::INFINITY);
+
+            // keys that are not strings are only reachable by index
+            let keys = yaml_hash_keys(doc);
+            assert_eq!(keys.length, 4);
+            yamlate_string_array_free(keys.value as *mut *const c_char, keys.length);
+            assert_eq!(yaml_hash_len(doc).value, 6);
+            let key = yaml_hash_key_at(doc, 3).value;
+            assert_eq!(yaml_integer_get(key).value, 1);
+            let pair = yaml_hash_key_at(doc, 4).value;
+            assert_eq!(yaml_array_len(pair).value, 2);
+            let value = yaml_hash_value_at(doc, 4).value;
+            assert_eq!(yaml_type(value), YamlType::String as i32);
+            assert_eq!(yaml_hash_key_at(doc, 6).error, Error::NotDefined as i32);
+            assert_eq!(yaml_hash_key_at(big, 0).error, Error::WrongType as i32);
+
+            // the values returned by yaml_get_path are borrowed from the document
+            for yaml in [key, pair, value, doc] {
+                yaml_destroy(yaml as *mut Yaml);
+            }
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        unsafe {
+            let bug = yaml_hash_create();
+            let legs = yaml_array_create();
+            assert_eq!(yaml_array_push(legs, yaml_integer_create(6)), 0);
+            assert_eq!(yaml_array_push(legs, yaml_decimal_create(2.5)), 0);
+            assert_eq!(yaml_array_push(legs, yaml_null_create()), 0);
+            assert_eq!(
+                yaml_hash_insert(bug, yaml_string_create(c"legs".as_ptr()), legs),
+                0
+            );
+            assert_eq!(
+                yaml_hash_insert(bug, yaml_boolean_create(true), yaml_boolean_create(false)),
+                0
+            );
+
+            // a failed call does not take the value
+            let name = yaml_string_create(c"beetle".as_ptr());
+            assert_eq!(yaml_array_push(bug, name), Error::WrongType as i32);
+            assert_eq!(
+                yaml_array_push(ptr::null_mut(), name),
+                Error::NullPointer as i32
+            );
+            assert!(yaml_string_create(ptr::null()).is_null());
+
+            let legs = vec![Yaml::Integer(6), Yaml::Real("2.5".to_owned()), Yaml::Null];
+            let mut expected = LinkedHashMap::new();
+            expected.insert(Yaml::String("legs".to_owned()), Yaml::Array(legs));
+            expected.insert(Yaml::Boolean(true), Yaml::Boolean(false));
+            assert_eq!(*bug, Yaml::Hash(expected));
+
+            yaml_destroy(name);
+            yaml_destroy(bug);
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        let s = c"bugs:\n  - name: '~> name'\n    legs: 6\n    span: 2.5\n    shiny: true\n";
+
+        unsafe {
+            let doc = yaml_create_from_string(s.as_ptr()).value;
+            let json = yaml_to_json(doc);
+            assert_eq!(
+                CStr::from_ptr(json).to_str(),
+                Ok("{\"bugs\":[{\"name\":\"~> name\",\"legs\":6,\"span\":2.5,\"shiny\":true}]}")
+            );
+            yamlate_string_free(json as *mut c_char);
+
+            let env = environment_create();
+            environment_set_string(env, c"name".as_ptr(), c"a \"b\"".as_ptr());
+            let name = yaml_evaluate_path(doc, c"bugs[0].name".as_ptr(), env).value;
+            let json = yaml_to_json(name);
+            assert_eq!(CStr::from_ptr(json).to_str(), Ok(r#""a \"b\"""#));
+            yamlate_string_free(json as *mut c_char);
+            assert!(yaml_to_json(ptr::null()).is_null());
+
+            yaml_destroy(name as *mut Yaml);
+            environment_destroy(env);
+            yaml_destroy(doc as *mut Yaml);
         }
     }
 }
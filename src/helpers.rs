@@ -32,10 +32,6 @@ lazy_static! {
     };
 }
 
-pub fn is_keyword(string: &str) -> bool {
-    matches!(string, "if" | "then" | "elif" | "else" | "do")
-}
-
 pub fn is_operator(string: &str) -> bool {
     OPERATORS.contains_key(string)
 }
@@ -77,38 +73,16 @@ pub fn operator_to_exp(operator: &str, exp1: Exp, exp2: Exp) -> Result<Exp, Yaml
     })
 }
 
-pub fn is_split_character(ch: char) -> bool {
-    matches!(ch, ' ' | '\n' | '\t')
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_is_keyword() {
-        assert_eq!(is_keyword("if"), true);
-        assert_eq!(is_keyword("then"), true);
-        assert_eq!(is_keyword("elif"), true);
-
-        assert_eq!(is_keyword("hello"), false);
-    }
-
     #[test]
     fn test_is_operator() {
-        assert_eq!(is_operator("="), true);
-        assert_eq!(is_operator("+"), true);
-
-        assert_eq!(is_operator("~"), false);
-    }
-
-    #[test]
-    fn test_is_split_character() {
-        assert_eq!(is_split_character(' '), true);
-        assert_eq!(is_split_character('\n'), true);
-        assert_eq!(is_split_character('\t'), true);
+        assert!(is_operator("="));
+        assert!(is_operator("+"));
 
-        assert_eq!(is_split_character('a'), false);
+        assert!(!is_operator("~"));
     }
 
     #[test]
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+/// An interned variable name
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Returns the index of the symbol (symbols are numbered from 0
+    /// in the order they were interned)
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Maps variable names to symbols so each name is only stored once
+#[derive(Default)]
+pub struct Interner {
+    symbols: HashMap<String, Symbol>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Returns the symbol for a name, adding the name if it is new
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.names.len() as u32);
+        self.symbols.insert(name.to_owned(), symbol);
+        self.names.push(name.to_owned());
+        symbol
+    }
+
+    /// Returns the symbol for a name if it has been interned
+    pub fn lookup(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).cloned()
+    }
+
+    /// Returns the name of a symbol or None if the symbol
+    /// was not interned by this interner
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.names.get(symbol.index()).map(String::as_str)
+    }
+
+    /// Returns the interned symbols and their names in the order they were interned
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+        (0..).map(Symbol).zip(self.names.iter().map(String::as_str))
+    }
+
+    /// Returns the number of interned names
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if no names have been interned
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+        let hello = interner.intern("hello");
+        let world = interner.intern("world");
+
+        assert_eq!(interner.intern("hello"), hello);
+        assert_ne!(hello, world);
+        assert_eq!(interner.lookup("world"), Some(world));
+        assert_eq!(interner.lookup("blah"), None);
+        assert_eq!(interner.resolve(world), Some("world"));
+        assert_eq!(interner.len(), 2);
+
+        let mut other = Interner::new();
+        for name in ["a", "b", "c"] {
+            other.intern(name);
+        }
+        assert_eq!(interner.resolve(other.intern("d")), None);
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::Write;
+use yaml_rust::yaml::Yaml;
+
+/// Converts YAML to a JSON string
+///
+/// Hash keys that are not strings are written as the JSON of the key inside a string,
+/// reals that are not finite and values that cannot be represented (aliases and
+/// bad values) are written as null
+pub fn to_json(yaml: &Yaml) -> String {
+    let mut s = String::new();
+    write_json(yaml, &mut s);
+    s
+}
+
+fn write_json(yaml: &Yaml, s: &mut String) {
+    match *yaml {
+        // {:?} always writes a fraction or an exponent, so a JSON reader
+        // does not turn a whole real like 2.0 into an integer
+        Yaml::Real(_) => match yaml.as_f64() {
+            Some(f) if f.is_finite() => write!(s, "{:?}", f).unwrap(),
+            _ => s.push_str("null"),
+        },
+        Yaml::Integer(i) => write!(s, "{}", i).unwrap(),
+        Yaml::String(ref string) => write_string(string, s),
+        Yaml::Boolean(b) => write!(s, "{}", b).unwrap(),
+        Yaml::Array(ref arr) => {
+            s.push('[');
+            for (i, val) in arr.iter().enumerate() {
+                if i > 0 {
+                    s.push(',');
+                }
+                write_json(val, s);
+            }
+            s.push(']');
+        }
+        Yaml::Hash(ref h) => {
+            s.push('{');
+            for (i, (key, val)) in h.iter().enumerate() {
+                if i > 0 {
+                    s.push(',');
+                }
+                match *key {
+                    Yaml::String(ref key) => write_string(key, s),
+                    ref key => write_string(&to_json(key), s),
+                }
+                s.push(':');
+                write_json(val, s);
+            }
+            s.push('}');
+        }
+        Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => s.push_str("null"),
+    }
+}
+
+fn write_string(string: &str, s: &mut String) {
+    s.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_to_json() {
+        let s = "
+        name: \"beetle\\n\"
+        legs: 6
+        weight: 2.0
+        colors: [red, ~, true]
+        3: three
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert_eq!(
+            to_json(&docs[0]),
+            r#"{"name":"beetle\n","legs":6,"weight":2.0,"colors":["red",null,true],"3":"three"}"#
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use crate::ast::Lit;
+use crate::environment::{ASTEnvironment, BindingInfo, ChangeLog, Environment, Snapshot};
+use std::sync::Arc;
+
+/// An environment that stacks a private mutable environment on top of
+/// a shared parent environment
+///
+/// The parent is never modified so it can be shared (like global state used by
+/// many documents) without copying it into every environment. Variables of the
+/// parent are read-only: they can be read like any other variable but assigning to
+/// them is an error like it is for constants (declaring one shadows it in the private
+/// environment). Scopes, snapshots and change tracking only apply to the private environment.
+///
+/// The scopes of the parent count as the outermost scopes of the environment:
+/// len includes them and the bindings of the private environment are deeper than
+/// the bindings of the parent, which are reported as read-only.
+pub struct LayeredEnvironment<P: Environment = ASTEnvironment> {
+    parent: Arc<P>,
+    local: ASTEnvironment,
+}
+
+impl<P: Environment> LayeredEnvironment<P> {
+    pub fn new(parent: Arc<P>) -> LayeredEnvironment<P> {
+        LayeredEnvironment {
+            parent,
+            local: ASTEnvironment::new(),
+        }
+    }
+
+    /// Returns the shared parent environment
+    pub fn parent(&self) -> &Arc<P> {
+        &self.parent
+    }
+
+    /// Returns the private environment stacked on top of the parent
+    pub fn local(&self) -> &ASTEnvironment {
+        &self.local
+    }
+
+    /// Same as local but mutable
+    pub fn local_mut(&mut self) -> &mut ASTEnvironment {
+        &mut self.local
+    }
+
+    /// Turns lenient assignment on or off
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.local.set_lenient(lenient);
+    }
+
+    /// Turns recording changes to variables on or off
+    pub fn set_track_changes(&mut self, track: bool) {
+        self.local.set_track_changes(track);
+    }
+}
+
+impl<P: Environment> Clone for LayeredEnvironment<P> {
+    fn clone(&self) -> Self {
+        LayeredEnvironment {
+            parent: self.parent.clone(),
+            local: self.local.clone(),
+        }
+    }
+}
+
+impl<P: Environment> Environment for LayeredEnvironment<P> {
+    fn get(&self, var: &str) -> Option<Lit> {
+        match self.local.get_ref(var) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.get(var),
+        }
+    }
+
+    fn contains(&self, var: &str) -> bool {
+        self.local.contains(var) || self.parent.contains(var)
+    }
+
+    fn assign(&mut self, var: &str, value: Lit) -> bool {
+        self.local.assign(var, value)
+    }
+
+    fn set(&mut self, var: &str, value: Lit) {
+        self.local.set(var, value);
+    }
+
+    fn set_const(&mut self, var: &str, value: Lit) {
+        self.local.set_const(var, value);
+    }
+
+    fn is_const(&self, var: &str) -> bool {
+        // like a constant of an outer scope, a parent variable can be
+        // shadowed by set or a declaration but not assigned to
+        if self.local.contains(var) {
+            self.local.is_const(var)
+        } else {
+            self.parent.contains(var)
+        }
+    }
+
+    fn is_const_in_scope(&self, var: &str) -> bool {
+        // the current scope is always a scope of the private environment
+        self.local.is_const_in_scope(var)
+    }
+
+    fn remove(&mut self, var: &str) -> Option<Lit> {
+        self.local.remove(var)
+    }
+
+    fn bindings(&self) -> Vec<BindingInfo> {
+        // the scopes of the private environment come after the scopes of the parent
+        let offset = self.parent.len();
+        let mut bindings = self
+            .local
+            .bindings()
+            .into_iter()
+            .map(|binding| BindingInfo {
+                depth: binding.depth + offset,
+                ..binding
+            })
+            .collect::<Vec<_>>();
+
+        for binding in self.parent.bindings() {
+            if !self.local.contains(&binding.name) {
+                bindings.push(BindingInfo {
+                    constant: true,
+                    ..binding
+                });
+            }
+        }
+
+        bindings.sort_by(|a, b| a.name.cmp(&b.name));
+        bindings
+    }
+
+    fn push(&mut self) {
+        self.local.push();
+    }
+
+    fn pop(&mut self) {
+        self.local.pop();
+    }
+
+    fn len(&self) -> usize {
+        self.parent.len() + self.local.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_lenient(&self) -> bool {
+        self.local.is_lenient()
+    }
+
+    fn snapshot(&mut self) -> Snapshot {
+        self.local.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) -> bool {
+        self.local.restore(snapshot)
+    }
+
+    fn commit(&mut self, snapshot: Snapshot) -> bool {
+        self.local.commit(snapshot)
+    }
+
+    fn change_log(&self) -> Option<&ChangeLog> {
+        self.local.change_log()
+    }
+
+    fn change_log_mut(&mut self) -> Option<&mut ChangeLog> {
+        self.local.change_log_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{EvalError, YamlError};
+    use crate::yaml::evaluate;
+    use yaml_rust::YamlLoader;
+    use yaml_rust::yaml::Yaml;
+
+    fn globals() -> Arc<ASTEnvironment> {
+        let mut globals = ASTEnvironment::new();
+        globals.set("season", Lit::Str("spring".to_owned()));
+        globals.set("temperature", Lit::Number(20));
+        Arc::new(globals)
+    }
+
+    #[test]
+    fn test_reads_parent() {
+        let s = "
+        wing_color:
+          if:
+            - '~> nearby == 1'
+            - do:
+              - '~> nearby = 0'
+              - '~> season'
+              else: blue
+        ";
+        let globals = globals();
+        let docs = YamlLoader::load_from_str(s).unwrap();
+
+        let mut env = LayeredEnvironment::new(globals.clone());
+        env.set("nearby", Lit::Number(1));
+        assert_eq!(
+            evaluate(&docs[0]["wing_color"], &mut env),
+            Ok(Yaml::String("spring".to_owned()))
+        );
+        assert_eq!(env.get("nearby"), Some(Lit::Number(0)));
+        assert_eq!(env.bindings().len(), 3);
+
+        // the parent is shared, not copied
+        let other = LayeredEnvironment::new(globals.clone());
+        assert_eq!(other.get("temperature"), Some(Lit::Number(20)));
+        assert_eq!(Arc::strong_count(&globals), 3);
+    }
+
+    #[test]
+    fn test_parent_is_read_only() {
+        let s = "
+        foo:
+          - '~> temperature = 30'
+        bar:
+          - '~> temperature := 30'
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let mut env = LayeredEnvironment::new(globals());
+
+        assert_eq!(
+            evaluate(&docs[0]["foo"], &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "temperature".to_owned()
+            )))
+        );
+        assert_eq!(env.parent().get("temperature"), Some(Lit::Number(20)));
+
+        // a declaration shadows the parent variable like it shadows an outer constant
+        let mut env = LayeredEnvironment::new(globals());
+        assert_eq!(evaluate(&docs[0]["bar"], &mut env), Ok(Yaml::Integer(30)));
+        assert_eq!(env.get("temperature"), Some(Lit::Number(30)));
+        assert_eq!(env.parent().get("temperature"), Some(Lit::Number(20)));
+
+        // the host can still shadow a parent variable
+        env.set("temperature", Lit::Number(30));
+        assert_eq!(env.get("temperature"), Some(Lit::Number(30)));
+        assert_eq!(env.remove("temperature"), Some(Lit::Number(30)));
+        assert_eq!(env.get("temperature"), Some(Lit::Number(20)));
+    }
+
+    #[test]
+    fn test_scopes_and_constants() {
+        let mut parent = ASTEnvironment::new();
+        parent.set("season", Lit::Str("spring".to_owned()));
+        parent.push();
+        parent.set("temperature", Lit::Number(20));
+
+        let mut env = LayeredEnvironment::new(Arc::new(parent));
+        env.set("nearby", Lit::Number(1));
+        env.push();
+        assert_eq!(env.len(), 4);
+
+        // every binding is in a scope len counts
+        let bindings = env.bindings();
+        assert!(bindings.iter().all(|binding| binding.depth < env.len()));
+        let depths = bindings
+            .iter()
+            .map(|binding| (binding.name.as_str(), binding.depth, binding.constant))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            depths,
+            vec![
+                ("nearby", 2, false),
+                ("season", 0, true),
+                ("temperature", 1, true)
+            ]
+        );
+
+        // a parent variable is read-only until it is shadowed
+        assert!(env.is_const("season"));
+        assert!(!env.is_const_in_scope("season"));
+        env.set("season", Lit::Str("winter".to_owned()));
+        assert!(!env.is_const("season"));
+        assert_eq!(env.bindings()[1].depth, 3);
+        env.pop();
+        assert!(env.is_const("season"));
+        assert_eq!(env.len(), 3);
+    }
+}
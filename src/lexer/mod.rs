@@ -20,6 +20,8 @@ pub struct LexerState {
     pub operators: VecDeque<String>,
     pub curr_state: WordState,
     pub curr_chars: Vec<char>,
+    /// Set when the expression starts with the const keyword
+    pub constant: bool,
 }
 
 impl LexerState {
@@ -29,6 +31,7 @@ impl LexerState {
             operators: VecDeque::new(),
             curr_state: WordState::None,
             curr_chars: Vec::new(),
+            constant: false,
         }
     }
 
@@ -62,6 +65,16 @@ impl Lexer {
             if ch == ' ' || ch == '\t' {
                 if self.state.curr_state == WordState::String {
                     self.state.curr_chars.push(ch);
+                } else if self.state.curr_state == WordState::Variable
+                    && !self.state.constant
+                    && self.state.variables.is_empty()
+                    && self.state.operators.is_empty()
+                    && self.state.curr_chars.iter().collect::<String>() == "const"
+                {
+                    // a leading const keyword marks the declaration as read-only
+                    self.state.emit_string();
+                    self.state.curr_state = WordState::None;
+                    self.state.constant = true;
                 }
             } else {
                 append_ch(ch, &mut self.state)?;
@@ -229,6 +242,27 @@ mod tests {
         assert_eq!(lexer.state.operators, operator_result);
     }
 
+    #[test]
+    fn test_const() {
+        let s = "const x := 2";
+        let mut lexer = Lexer::new();
+        assert_eq!(lexer.parse_string(s), Ok(()));
+        assert!(lexer.state.constant);
+
+        let variable_result: VecDeque<Exp> =
+            vec![Exp::Variable("x".to_owned()), Exp::Lit(Lit::Number(2))]
+                .into_iter()
+                .rev()
+                .collect();
+        assert_eq!(lexer.state.variables, variable_result);
+
+        // const is only a keyword at the start of an expression
+        let s = "constant := 2";
+        let mut lexer = Lexer::new();
+        assert_eq!(lexer.parse_string(s), Ok(()));
+        assert!(!lexer.state.constant);
+    }
+
     #[test]
     fn test_float() {
         let s = "1.23 - 3.12 + 123.45678";
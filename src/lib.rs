@@ -1,6 +1,39 @@
+//! Evaluates YAML documents with embedded expressions given variable bindings
+//!
+//! The most common types and functions are re-exported here:
+//!
+//! * [`Engine`] loads [`Document`]s and evaluates them with its limits and
+//!   registered functions
+//! * [`Environment`] stores the variables of an evaluation, [`ASTEnvironment`]
+//!   is the default implementation
+//! * [`Lit`] is a variable value and [`Exp`] a parsed expression
+//! * [`evaluate`] evaluates a document and [`evaluate_path`] a part of it
+//! * [`parse`] parses an expression, [`Chunk::compile`] compiles one to
+//!   bytecode and [`Program::compile`] compiles a document to share it
+//!   between threads
+//! * [`YamlError`] is returned when parsing or evaluating fails
+//!
+//! ```
+//! use yamlate::{ASTEnvironment, Environment, Lit, evaluate};
+//! use yaml_rust::YamlLoader;
+//! use yaml_rust::yaml::Yaml;
+//!
+//! let docs = YamlLoader::load_from_str("legs: '~> pairs * 2'").unwrap();
+//! let mut env = ASTEnvironment::new();
+//! env.set("pairs", Lit::Number(3));
+//!
+//! assert_eq!(evaluate(&docs[0]["legs"], &mut env), Ok(Yaml::Integer(6)));
+//! ```
+//!
+//! Rust values can be converted to and from environments and documents with
+//! the functions in the `serde` module behind the `serde` feature.
+//!
+//! The C API is in the [`ffi`] module behind the `ffi` feature (on by default).
+
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(feature = "ffi")]
 extern crate libc;
 extern crate linked_hash_map;
 extern crate num;
@@ -12,6 +45,32 @@ mod helpers;
 mod lexer;
 mod parser;
 
+pub mod checker;
+pub mod engine;
 pub mod environment;
+#[cfg(feature = "ffi")]
 pub mod ffi;
+pub mod interner;
+pub mod json;
+pub mod layered_environment;
+pub mod optimizer;
+pub mod path;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod slot_environment;
+pub mod vm;
 pub mod yaml;
+
+pub use crate::ast::{Exp, Lit, LitType, Op};
+pub use crate::engine::{Document, Engine, Limits, Value};
+pub use crate::environment::{ASTEnvironment, Environment};
+#[cfg(feature = "serde")]
+pub use crate::errors::SerdeError;
+pub use crate::errors::{EvalError, LexError, LoadError, PathError, YamlError};
+pub use crate::vm::{Chunk, Program};
+pub use crate::yaml::{evaluate, evaluate_batch, evaluate_path, evaluate_with};
+
+/// Parses an expression (without the '~>' prefix) into its syntax tree
+pub fn parse(s: &str) -> Result<Exp, YamlError> {
+    parser::compile(s)
+}
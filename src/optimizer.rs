@@ -0,0 +1,148 @@
+use crate::ast::{Exp, Lit, Op};
+
+/// Simplifies an expression without changing the result of evaluating it:
+/// operators applied only to literals are folded into a literal,
+/// and boolean identities (x && true, x || false, !!x) are removed
+///
+/// Operators that fail on their literals (like a division by zero) are left
+/// as they are so they only fail if they are evaluated, the checker reports
+/// the divisions by zero left in the folded expression
+pub fn optimize(exp: Exp) -> Exp {
+    match exp {
+        Exp::UnaryOp(op, exp) => {
+            let exp = optimize(*exp);
+            match exp {
+                Exp::Lit(ref value) => match value.clone().apply_unary(op) {
+                    Ok(lit) => Exp::Lit(lit),
+                    // leave type errors to be reported when evaluating
+                    Err(_) => Exp::UnaryOp(op, Box::new(exp)),
+                },
+                Exp::UnaryOp(Op::Not, inner) if op == Op::Not && is_bool(&inner) => *inner,
+                exp => Exp::UnaryOp(op, Box::new(exp)),
+            }
+        }
+        Exp::BinaryOp(op, exp1, exp2) => {
+            let (exp1, exp2) = (optimize(*exp1), optimize(*exp2));
+
+            match (op, exp1, exp2) {
+                (op, Exp::Lit(val1), Exp::Lit(val2)) => {
+                    match val1.clone().apply_binary(op, val2.clone()) {
+                        Ok(lit) => Exp::Lit(lit),
+                        // leave type errors and division by zero to be reported when evaluating
+                        Err(_) => {
+                            Exp::BinaryOp(op, Box::new(Exp::Lit(val1)), Box::new(Exp::Lit(val2)))
+                        }
+                    }
+                }
+                (Op::And, exp, Exp::Lit(Lit::Bool(true)))
+                | (Op::And, Exp::Lit(Lit::Bool(true)), exp)
+                | (Op::Or, exp, Exp::Lit(Lit::Bool(false)))
+                | (Op::Or, Exp::Lit(Lit::Bool(false)), exp)
+                    if is_bool(&exp) =>
+                {
+                    exp
+                }
+                (op, exp1, exp2) => Exp::BinaryOp(op, Box::new(exp1), Box::new(exp2)),
+            }
+        }
+        Exp::Declare(name, exp) => Exp::Declare(name, Box::new(optimize(*exp))),
+        Exp::DeclareConst(name, exp) => Exp::DeclareConst(name, Box::new(optimize(*exp))),
+        Exp::Assign(name, exp) => Exp::Assign(name, Box::new(optimize(*exp))),
+        exp @ (Exp::Variable(_) | Exp::Lit(_)) => exp,
+    }
+}
+
+// returns true if the expression can only evaluate to a boolean (or fail),
+// so removing an identity operator applied to it does not change the result
+fn is_bool(exp: &Exp) -> bool {
+    matches!(
+        *exp,
+        Exp::Lit(Lit::Bool(_))
+            | Exp::UnaryOp(Op::Not, _)
+            | Exp::BinaryOp(Op::And | Op::Or | Op::Equal | Op::NotEqual, _, _)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Box<Exp> {
+        Box::new(Exp::Variable(name.to_owned()))
+    }
+
+    fn num(n: i32) -> Box<Exp> {
+        Box::new(Exp::Lit(Lit::Number(n)))
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        // Test that `a * (2 + 3)` becomes `a * 5`
+        let exp = Exp::BinaryOp(
+            Op::Times,
+            var("a"),
+            Box::new(Exp::BinaryOp(Op::Plus, num(2), num(3))),
+        );
+        assert_eq!(optimize(exp), Exp::BinaryOp(Op::Times, var("a"), num(5)));
+
+        // Test that `x := !(1 == 2)` becomes `x := true`
+        let exp = Exp::Declare(
+            "x".to_owned(),
+            Box::new(Exp::UnaryOp(
+                Op::Not,
+                Box::new(Exp::BinaryOp(Op::Equal, num(1), num(2))),
+            )),
+        );
+        assert_eq!(
+            optimize(exp),
+            Exp::Declare("x".to_owned(), Box::new(Exp::Lit(Lit::Bool(true))))
+        );
+    }
+
+    #[test]
+    fn test_type_errors_not_folded() {
+        // Test that `"spring" - 1` is left for the evaluator to report
+        let exp = Exp::BinaryOp(
+            Op::Minus,
+            Box::new(Exp::Lit(Lit::Str("spring".to_owned()))),
+            num(1),
+        );
+        assert_eq!(optimize(exp.clone()), exp);
+    }
+
+    #[test]
+    fn test_boolean_identities() {
+        let cmp = || Box::new(Exp::BinaryOp(Op::Equal, var("a"), num(2)));
+
+        // `a == 2 && true` becomes `a == 2`
+        let exp = Exp::BinaryOp(Op::And, cmp(), Box::new(Exp::Lit(Lit::Bool(true))));
+        assert_eq!(optimize(exp), *cmp());
+
+        // `false || (a == 2)` becomes `a == 2`
+        let exp = Exp::BinaryOp(Op::Or, Box::new(Exp::Lit(Lit::Bool(false))), cmp());
+        assert_eq!(optimize(exp), *cmp());
+
+        // `!!(a == 2)` becomes `a == 2`
+        let exp = Exp::UnaryOp(Op::Not, Box::new(Exp::UnaryOp(Op::Not, cmp())));
+        assert_eq!(optimize(exp), *cmp());
+
+        // `a && true` is kept because a might not be a boolean
+        let exp = Exp::BinaryOp(Op::And, var("a"), Box::new(Exp::Lit(Lit::Bool(true))));
+        assert_eq!(optimize(exp.clone()), exp);
+    }
+
+    #[test]
+    fn test_division_by_zero_not_folded() {
+        // Test that `a / (2 - 2)` becomes `a / 0` instead of failing
+        // so it only fails if it is evaluated
+        let exp = Exp::BinaryOp(
+            Op::Divide,
+            var("a"),
+            Box::new(Exp::BinaryOp(Op::Minus, num(2), num(2))),
+        );
+        assert_eq!(optimize(exp), Exp::BinaryOp(Op::Divide, var("a"), num(0)));
+
+        let exp = Exp::BinaryOp(Op::Modulo, num(5), num(0));
+        assert_eq!(optimize(exp.clone()), exp);
+    }
+}
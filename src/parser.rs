@@ -1,8 +1,31 @@
 use crate::ast::{Exp, Op};
 use crate::errors::{LexError, YamlError};
 use crate::helpers::{operator_precedence, operator_to_exp};
+use crate::lexer::Lexer;
+use crate::optimizer::optimize;
 use std::collections::VecDeque;
 
+/// Lexes and parses an expression string (without the "~>" prefix) into an
+/// optimized AST
+pub fn compile(s: &str) -> Result<Exp, YamlError> {
+    let mut lexer = Lexer::new();
+    lexer.parse_string(s)?;
+
+    let mut parser = Parser::new();
+    let ast = parser.parse_to_ast(&mut lexer.state.variables, &mut lexer.state.operators)?;
+
+    let ast = if lexer.state.constant {
+        match ast {
+            Exp::Declare(name, exp) => Exp::DeclareConst(name, exp),
+            _ => return Err(YamlError::LexError(LexError::ConstNotDeclare)),
+        }
+    } else {
+        ast
+    };
+
+    Ok(optimize(ast))
+}
+
 /// Parses string into AST
 pub struct Parser {
     var_stack: VecDeque<Exp>,
@@ -83,15 +106,13 @@ impl Parser {
             // an operator with precedence -1 ignores precedence rules
             let operator = operators.pop_back().unwrap();
 
-            if !self.op_stack.is_empty() {
-                if let Some(front_operator) = self.op_stack.front()
-                    && operator_precedence(operator.as_str())
-                        < operator_precedence(front_operator.as_str())
-                    && operator_precedence(operator.as_str()) != -1
-                {
-                    lower_precedence = true;
-                    op_precedence = operator_precedence(operator.as_str());
-                }
+            if let Some(front_operator) = self.op_stack.front()
+                && operator_precedence(operator.as_str())
+                    < operator_precedence(front_operator.as_str())
+                && operator_precedence(operator.as_str()) != -1
+            {
+                lower_precedence = true;
+                op_precedence = operator_precedence(operator.as_str());
             }
 
             if lower_precedence {
@@ -261,6 +282,53 @@ mod tests {
         assert_eq!(result, Ok(expected_val));
     }
 
+    #[test]
+    fn test_compile_const() {
+        assert_eq!(
+            compile(" const x := a + 2"),
+            Ok(Exp::DeclareConst(
+                "x".to_owned(),
+                Box::new(Exp::BinaryOp(
+                    Op::Plus,
+                    Box::new(Exp::Variable("a".to_owned())),
+                    Box::new(Exp::Lit(Lit::Number(2))),
+                )),
+            ))
+        );
+        assert_eq!(
+            compile("x := 1 + 2"),
+            Ok(Exp::Declare(
+                "x".to_owned(),
+                Box::new(Exp::Lit(Lit::Number(3)))
+            ))
+        );
+        assert_eq!(
+            compile("const x = 1"),
+            Err(YamlError::LexError(LexError::ConstNotDeclare))
+        );
+    }
+
+    #[test]
+    fn test_const_is_reserved() {
+        // const is a keyword at the start of an expression so a variable
+        // named const can only be used after it
+        assert_eq!(
+            compile("const := 1"),
+            Err(YamlError::LexError(LexError::VariableStackError))
+        );
+        assert_eq!(
+            compile("const + 1"),
+            Err(YamlError::LexError(LexError::VariableStackError))
+        );
+        assert_eq!(
+            compile("x := const"),
+            Ok(Exp::Declare(
+                "x".to_owned(),
+                Box::new(Exp::Variable("const".to_owned()))
+            ))
+        );
+    }
+
     #[test]
     fn test_parse_to_ast_simple() {
         // test ast generation for `1 + 2`
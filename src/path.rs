@@ -0,0 +1,135 @@
+use crate::errors::PathError;
+use yaml_rust::yaml::Yaml;
+
+/// A step of a path into a YAML document
+#[derive(Debug, PartialEq, Clone)]
+pub enum Segment {
+    /// A key of a hash (integer keys can also be looked up by their text)
+    Key(String),
+    /// An index of an array
+    Index(usize),
+}
+
+/// A path into a YAML document made of hash keys separated by dots
+/// and array indices in brackets ("cricket.wing_color", "bugs[3].name")
+#[derive(Debug, PartialEq, Clone)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+impl Path {
+    pub fn parse(s: &str) -> Result<Path, PathError> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        let mut first = true;
+
+        loop {
+            // a key runs until the next dot or bracket
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            let (key, after) = rest.split_at(end);
+            if !key.is_empty() {
+                segments.push(Segment::Key(key.to_owned()));
+            } else if !(first && after.starts_with('[')) {
+                // only a path starting with an index has no key before the brackets
+                return Err(PathError::EmptyKey);
+            }
+            rest = after;
+            first = false;
+
+            while let Some(index) = rest.strip_prefix('[') {
+                let close = index.find(']').ok_or(PathError::UnclosedBracket)?;
+                let index_str = &index[..close];
+                let i = index_str
+                    .parse::<usize>()
+                    .map_err(|_| PathError::InvalidIndex(index_str.to_owned()))?;
+                segments.push(Segment::Index(i));
+                rest = &index[close + 1..];
+            }
+
+            match rest.strip_prefix('.') {
+                Some(after) => rest = after,
+                None if rest.is_empty() => return Ok(Path { segments }),
+                None => return Err(PathError::EmptyKey),
+            }
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the value at the path or None if a key or index is missing
+    pub fn get<'a>(&self, yaml: &'a Yaml) -> Option<&'a Yaml> {
+        self.segments
+            .iter()
+            .try_fold(yaml, |yaml, segment| match (segment, yaml) {
+                (Segment::Key(key), Yaml::Hash(h)) => h
+                    .get(&Yaml::String(key.clone()))
+                    .or_else(|| h.get(&Yaml::Integer(key.parse().ok()?))),
+                (Segment::Index(i), Yaml::Array(arr)) => arr.get(*i),
+                _ => None,
+            })
+    }
+}
+
+/// Returns the value at a path of the document or None if the value
+/// is missing, fails if the path is not valid
+pub fn get_path<'a>(yaml: &'a Yaml, path: &str) -> Result<Option<&'a Yaml>, PathError> {
+    Ok(Path::parse(path)?.get(yaml))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Path::parse("bugs[3][0].name").map(|p| p.segments),
+            Ok(vec![
+                Segment::Key("bugs".to_owned()),
+                Segment::Index(3),
+                Segment::Index(0),
+                Segment::Key("name".to_owned()),
+            ])
+        );
+        assert_eq!(
+            Path::parse("[1]").map(|p| p.segments),
+            Ok(vec![Segment::Index(1)])
+        );
+
+        assert_eq!(Path::parse(""), Err(PathError::EmptyKey));
+        assert_eq!(Path::parse("a..b"), Err(PathError::EmptyKey));
+        assert_eq!(Path::parse("a."), Err(PathError::EmptyKey));
+        assert_eq!(Path::parse("a[1]b"), Err(PathError::EmptyKey));
+        assert_eq!(Path::parse("a.[1]"), Err(PathError::EmptyKey));
+        assert_eq!(Path::parse("a[1"), Err(PathError::UnclosedBracket));
+        assert_eq!(
+            Path::parse("a[-1]"),
+            Err(PathError::InvalidIndex("-1".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_get_path() {
+        let s = "
+        cricket:
+          wing_color: red
+        bugs:
+          - name: beetle
+          - name: ant
+        3: three
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let get = |path| get_path(&docs[0], path).map(|yaml| yaml.and_then(|yaml| yaml.as_str()));
+
+        assert_eq!(get("cricket.wing_color"), Ok(Some("red")));
+        assert_eq!(get("bugs[1].name"), Ok(Some("ant")));
+        assert_eq!(get("3"), Ok(Some("three")));
+        assert_eq!(get("bugs[2].name"), Ok(None));
+        assert_eq!(get("cricket[0]"), Ok(None));
+        assert_eq!(get("cricket..wing_color"), Err(PathError::EmptyKey));
+        assert_eq!(get("bugs[1"), Err(PathError::UnclosedBracket));
+    }
+}
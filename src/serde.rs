@@ -0,0 +1,750 @@
+//! Converts Rust values to and from YAML and environments with serde
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use yamlate::serde::{from_environment, to_environment};
+//! use yamlate::{ASTEnvironment, Environment};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Cricket {
+//!     legs: i32,
+//!     season: String,
+//! }
+//!
+//! let mut env = ASTEnvironment::new();
+//! let cricket = Cricket { legs: 6, season: "spring".to_owned() };
+//! to_environment(&cricket, &mut env).unwrap();
+//!
+//! assert_eq!(from_environment::<Cricket, _>(&env), Ok(cricket));
+//! ```
+
+use crate::ast::Lit;
+use crate::environment::Environment;
+use crate::errors::{LoadError, SerdeError};
+use linked_hash_map::{self, LinkedHashMap};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::ser::{self, Serialize};
+use serde::{Deserialize, forward_to_deserialize_any};
+use std::slice;
+use yaml_rust::yaml::Yaml;
+
+/// Converts a value to YAML
+///
+/// Structs and maps become hashes, sequences and tuples become arrays,
+/// None and () become null and enum variants are written like serde_yaml
+/// writes them (the name of a unit variant or a hash with the name as its only key)
+pub fn to_yaml<T: Serialize + ?Sized>(value: &T) -> Result<Yaml, SerdeError> {
+    value.serialize(Serializer)
+}
+
+/// Converts YAML (like the result of an evaluation) to a value
+pub fn from_yaml<'de, T: Deserialize<'de>>(yaml: &'de Yaml) -> Result<T, SerdeError> {
+    T::deserialize(Deserializer { yaml })
+}
+
+/// Sets every field of a struct (or entry of a map) as a variable in the current
+/// scope of the environment, the fields have to be scalars
+pub fn to_environment<T: Serialize + ?Sized>(
+    value: &T,
+    env: &mut impl Environment,
+) -> Result<(), SerdeError> {
+    let h = match to_yaml(value)? {
+        Yaml::Hash(h) => h,
+        _ => return Err(SerdeError::LoadError(LoadError::NotMapping)),
+    };
+
+    for (key, val) in h {
+        let name = match key {
+            Yaml::String(name) => name,
+            _ => return Err(SerdeError::LoadError(LoadError::NameNotString)),
+        };
+        match Lit::from_yaml(&val) {
+            Some(lit) => env.set(&name, lit),
+            None => return Err(SerdeError::LoadError(LoadError::UnsupportedValue(name))),
+        }
+    }
+    Ok(())
+}
+
+/// Converts the variables visible from the current scope of the environment
+/// to a value (every variable is a field of the struct)
+pub fn from_environment<T: DeserializeOwned, E: Environment>(env: &E) -> Result<T, SerdeError> {
+    let mut h = LinkedHashMap::new();
+    for binding in env.bindings() {
+        if let Some(value) = env.get(&binding.name) {
+            h.insert(Yaml::String(binding.name), Yaml::from(value));
+        }
+    }
+    from_yaml(&Yaml::Hash(h))
+}
+
+/// Serializes values to YAML
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeHash;
+    type SerializeStruct = SerializeHash;
+    type SerializeStructVariant = SerializeHash;
+
+    fn serialize_bool(self, v: bool) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Yaml, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Yaml, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Yaml, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Yaml, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Yaml, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Yaml, SerdeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Yaml, SerdeError> {
+        i64::try_from(v)
+            .map(Yaml::Integer)
+            .map_err(|_| SerdeError::Unsupported(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Yaml, SerdeError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::from(Lit::Decimal(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::Array(
+            v.iter().map(|&b| Yaml::Integer(b.into())).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Yaml, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Yaml, SerdeError> {
+        Ok(Yaml::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Yaml, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Yaml, SerdeError> {
+        Ok(tag(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray {
+            arr: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray {
+            arr: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<SerializeHash, SerdeError> {
+        Ok(SerializeHash {
+            hash: LinkedHashMap::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeHash, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<SerializeHash, SerdeError> {
+        Ok(SerializeHash {
+            hash: LinkedHashMap::new(),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+// wraps the value of an enum variant in a hash with the name of the variant as its key
+fn tag(variant: &str, value: Yaml) -> Yaml {
+    let mut h = LinkedHashMap::new();
+    h.insert(Yaml::String(variant.to_owned()), value);
+    Yaml::Hash(h)
+}
+
+/// Serializes sequences, tuples and tuple variants to an array
+pub struct SerializeArray {
+    arr: Vec<Yaml>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeArray {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.arr.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Yaml, SerdeError> {
+        let arr = Yaml::Array(self.arr);
+        Ok(match self.variant {
+            Some(variant) => tag(variant, arr),
+            None => arr,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+/// Serializes maps, structs and struct variants to a hash
+pub struct SerializeHash {
+    hash: LinkedHashMap<Yaml, Yaml>,
+    // the key of the entry being serialized by serialize_key and serialize_value
+    key: Option<Yaml>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeHash {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), SerdeError> {
+        self.hash
+            .insert(Yaml::String(key.to_owned()), value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Yaml, SerdeError> {
+        let hash = Yaml::Hash(self.hash);
+        Ok(match self.variant {
+            Some(variant) => tag(variant, hash),
+            None => hash,
+        })
+    }
+}
+
+impl ser::SerializeMap for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key = Some(key.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeError::Message("Map value serialized before its key".to_owned()))?;
+        self.hash.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Yaml, SerdeError> {
+        self.finish()
+    }
+}
+
+/// Deserializes values from borrowed YAML
+pub struct Deserializer<'de> {
+    yaml: &'de Yaml,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(yaml: &'de Yaml) -> Deserializer<'de> {
+        Deserializer { yaml }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match *self.yaml {
+            Yaml::Integer(i) => visitor.visit_i64(i),
+            Yaml::Real(ref s) => match self.yaml.as_f64() {
+                Some(f) => visitor.visit_f64(f),
+                None => Err(SerdeError::Message(format!("Invalid real {:?}", s))),
+            },
+            Yaml::String(ref s) => visitor.visit_borrowed_str(s),
+            Yaml::Boolean(b) => visitor.visit_bool(b),
+            Yaml::Array(ref arr) => visitor.visit_seq(ArrayAccess { iter: arr.iter() }),
+            Yaml::Hash(ref h) => visitor.visit_map(HashAccess {
+                iter: h.iter(),
+                value: None,
+            }),
+            Yaml::Null => visitor.visit_unit(),
+            Yaml::Alias(_) | Yaml::BadValue => Err(SerdeError::Message(format!(
+                "Cannot convert {:?} from YAML",
+                self.yaml
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match *self.yaml {
+            Yaml::Null | Yaml::BadValue => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match *self.yaml {
+            Yaml::String(ref variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+            Yaml::Hash(ref h) if h.len() == 1 => {
+                let (variant, value) = h.iter().next().unwrap();
+                visitor.visit_enum(VariantDeserializer { variant, value })
+            }
+            _ => Err(de::Error::invalid_type(
+                de::Unexpected::Other("YAML that is not a string or a hash with one key"),
+                &"an enum variant",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for Deserializer<'de> {
+    type Deserializer = Deserializer<'de>;
+
+    fn into_deserializer(self) -> Deserializer<'de> {
+        self
+    }
+}
+
+struct ArrayAccess<'de> {
+    iter: slice::Iter<'de, Yaml>,
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        match self.iter.next() {
+            Some(yaml) => seed.deserialize(Deserializer { yaml }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct HashAccess<'de> {
+    iter: linked_hash_map::Iter<'de, Yaml, Yaml>,
+    // the value of the entry whose key was just deserialized
+    value: Option<&'de Yaml>,
+}
+
+impl<'de> MapAccess<'de> for HashAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Deserializer { yaml: key }).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.value.take() {
+            Some(yaml) => seed.deserialize(Deserializer { yaml }),
+            None => Err(SerdeError::Message(
+                "Map value deserialized before its key".to_owned(),
+            )),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+// an enum variant written as a hash with the name of the variant as its only key
+struct VariantDeserializer<'de> {
+    variant: &'de Yaml,
+    value: &'de Yaml,
+}
+
+impl<'de> EnumAccess<'de> for VariantDeserializer<'de> {
+    type Error = SerdeError;
+    type Variant = Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer<'de>), SerdeError> {
+        let variant = seed.deserialize(Deserializer { yaml: self.variant })?;
+        Ok((variant, Deserializer { yaml: self.value }))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Deserializer<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::environment::ASTEnvironment;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Season {
+        Spring,
+        Winter,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Inputs {
+        pairs: u8,
+        span: f64,
+        season: Season,
+        name: String,
+        nocturnal: bool,
+        mate: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Wing {
+        Folded,
+        Spread(f64),
+        Colored { front: String, back: String },
+        Pair(i32, i32),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Beetle {
+        legs: i64,
+        wings: Vec<Wing>,
+        tags: BTreeMap<String, bool>,
+    }
+
+    #[test]
+    fn test_environment() {
+        let inputs = Inputs {
+            pairs: 3,
+            span: 2.5,
+            season: Season::Spring,
+            name: "beetle".to_owned(),
+            nocturnal: false,
+            mate: None,
+        };
+        let mut env = ASTEnvironment::new();
+        to_environment(&inputs, &mut env).unwrap();
+
+        assert_eq!(env.get("pairs"), Some(Lit::Number(3)));
+        assert_eq!(env.get("span"), Some(Lit::Decimal(2.5)));
+        assert_eq!(env.get("season"), Some(Lit::Str("Spring".to_owned())));
+        assert_eq!(env.get("mate"), Some(Lit::Nil));
+        assert_eq!(from_environment::<Inputs, _>(&env), Ok(inputs));
+
+        env.set("season", Lit::Str("Summer".to_owned()));
+        assert!(from_environment::<Inputs, _>(&env).is_err());
+
+        assert_eq!(
+            to_environment(&[1, 2], &mut env),
+            Err(SerdeError::LoadError(LoadError::NotMapping))
+        );
+        let wings = Beetle {
+            legs: 6,
+            wings: vec![Wing::Folded],
+            tags: BTreeMap::new(),
+        };
+        assert_eq!(
+            to_environment(&wings, &mut env),
+            Err(SerdeError::LoadError(LoadError::UnsupportedValue(
+                "wings".to_owned()
+            )))
+        );
+        assert_eq!(
+            to_yaml(&u64::MAX),
+            Err(SerdeError::Unsupported(u64::MAX.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_yaml() {
+        let mut tags = BTreeMap::new();
+        tags.insert("shiny".to_owned(), true);
+        let beetle = Beetle {
+            legs: 6,
+            wings: vec![
+                Wing::Folded,
+                Wing::Spread(1.5),
+                Wing::Colored {
+                    front: "red".to_owned(),
+                    back: "black".to_owned(),
+                },
+                Wing::Pair(1, 2),
+            ],
+            tags,
+        };
+
+        let yaml = to_yaml(&beetle).unwrap();
+        assert_eq!(yaml["wings"][0], Yaml::String("Folded".to_owned()));
+        assert_eq!(yaml["wings"][1]["Spread"], Yaml::Real("1.5".to_owned()));
+        assert_eq!(
+            yaml["wings"][2]["Colored"]["back"],
+            Yaml::String("black".to_owned())
+        );
+        assert_eq!(from_yaml::<Beetle>(&yaml), Ok(beetle));
+
+        // results of evaluations
+        let s = "
+        legs: '~> pairs * 2'
+        wing:
+          - if:
+            - '~> pairs == 3'
+            - do:
+              - Folded
+        tags:
+          shiny: true
+          spotted: false
+        ";
+        let engine = Engine::new();
+        let doc = engine.load_str(s).unwrap();
+        let mut env = ASTEnvironment::new();
+        env.set("pairs", Lit::Number(3));
+
+        let value = doc.eval_path("legs", &mut env).unwrap();
+        assert_eq!(value.deserialize::<u8>(), Ok(6));
+        let value = doc.eval_path("wing", &mut env).unwrap();
+        assert_eq!(value.deserialize::<Wing>(), Ok(Wing::Folded));
+        let tags: BTreeMap<String, bool> = from_yaml(doc.get("tags").unwrap().unwrap()).unwrap();
+        assert_eq!(tags.get("spotted"), Some(&false));
+
+        let legs: Result<String, _> = doc.eval_path("legs", &mut env).unwrap().deserialize();
+        assert!(legs.is_err());
+    }
+}
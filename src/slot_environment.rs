@@ -0,0 +1,494 @@
+use crate::ast::Lit;
+use crate::environment::{BindingInfo, ChangeLog, Environment, Journal, Snapshot};
+use crate::interner::{Interner, Symbol};
+use crate::vm::Chunk;
+
+#[derive(Clone)]
+struct SlotBinding {
+    value: Lit,
+    constant: bool,
+    /// The scope the binding was set in
+    depth: usize,
+}
+
+enum Undo {
+    /// Puts back the previous binding of a slot, removing the shadowed
+    /// binding recorded in the current scope if the change added one
+    Slot(Symbol, Option<SlotBinding>, bool),
+    /// Removes a pushed scope
+    Push,
+    /// Adds back a popped scope along with the bindings
+    /// its slots had before it was popped
+    Pop(Vec<(Symbol, Option<SlotBinding>)>, Vec<Option<SlotBinding>>),
+    /// Puts back a removed binding and the shadowed binding
+    /// recorded at the given position of its scope
+    Remove(Symbol, SlotBinding, usize, Option<SlotBinding>),
+}
+
+/// An environment that stores the current binding of every variable in
+/// a slot indexed by its interned symbol
+///
+/// Unlike ASTEnvironment, looking up a variable does not walk the scopes
+/// and the symbol based methods (get_slot, set_slot, assign_slot) do not
+/// hash or allocate, so symbols can be resolved once (like with bind for
+/// compiled chunks) and reused inside loops
+pub struct SlotEnvironment {
+    interner: Interner,
+    /// The current binding of each symbol indexed by the symbol
+    slots: Vec<Option<SlotBinding>>,
+    /// For each scope, the bindings that were shadowed by bindings set
+    /// in the scope so they can be restored when the scope is popped
+    scopes: Vec<Vec<(Symbol, Option<SlotBinding>)>>,
+    lenient: bool,
+    journal: Journal<Undo>,
+    changes: Option<ChangeLog>,
+}
+
+impl SlotEnvironment {
+    pub fn new() -> SlotEnvironment {
+        SlotEnvironment {
+            interner: Interner::new(),
+            slots: Vec::new(),
+            scopes: vec![Vec::new()],
+            lenient: false,
+            journal: Journal::new(),
+            changes: None,
+        }
+    }
+
+    /// Turns lenient assignment on or off
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Turns recording changes to variables on or off
+    /// (turning it off discards the recorded changes)
+    pub fn set_track_changes(&mut self, track: bool) {
+        match (track, &self.changes) {
+            (true, None) => self.changes = Some(ChangeLog::new()),
+            (false, _) => self.changes = None,
+            _ => {}
+        }
+    }
+
+    /// Returns the symbol for a variable name
+    pub fn symbol(&mut self, name: &str) -> Symbol {
+        let symbol = self.interner.intern(name);
+        if self.slots.len() < self.interner.len() {
+            self.slots.resize_with(self.interner.len(), || None);
+        }
+        symbol
+    }
+
+    /// Returns the name of a symbol or None if the symbol
+    /// does not belong to this environment
+    pub fn name(&self, symbol: Symbol) -> Option<&str> {
+        self.interner.resolve(symbol)
+    }
+
+    /// Resolves the variable slots of a compiled chunk to symbols
+    /// so the chunk can be run with Vm::run_bound
+    pub fn bind(&mut self, chunk: &Chunk) -> Vec<Symbol> {
+        chunk.names().iter().map(|name| self.symbol(name)).collect()
+    }
+
+    /// Gets the value of the current binding of a symbol
+    pub fn get_slot(&self, symbol: Symbol) -> Option<&Lit> {
+        match self.slots.get(symbol.index()) {
+            Some(Some(binding)) => Some(&binding.value),
+            _ => None,
+        }
+    }
+
+    /// Same as get but borrows the value instead of cloning it
+    pub fn get_ref(&self, var: &str) -> Option<&Lit> {
+        self.interner
+            .lookup(var)
+            .and_then(|symbol| self.get_slot(symbol))
+    }
+
+    /// Returns true if the current binding of a symbol is read-only
+    pub fn is_const_slot(&self, symbol: Symbol) -> bool {
+        matches!(self.slots.get(symbol.index()), Some(Some(binding)) if binding.constant)
+    }
+
+    /// Returns true if the binding of a symbol in the current scope is read-only
+    pub fn is_const_in_scope_slot(&self, symbol: Symbol) -> bool {
+        matches!(
+            self.slots.get(symbol.index()),
+            Some(Some(binding)) if binding.constant && binding.depth + 1 == self.scopes.len()
+        )
+    }
+
+    /// Sets a binding from a symbol to a value in the current scope
+    /// returns false if the symbol does not belong to this environment
+    /// or there is no scope to set it in
+    pub fn set_slot(&mut self, symbol: Symbol, value: Lit, constant: bool) -> bool {
+        let depth = match self.scopes.len() {
+            0 => return false,
+            n => n - 1,
+        };
+        let Some(slot) = self.slots.get_mut(symbol.index()) else {
+            return false;
+        };
+
+        if let Some(log) = self.changes.as_mut()
+            && let Some(name) = self.interner.resolve(symbol)
+        {
+            let old = slot.as_ref().map(|b| b.value.clone());
+            log.record(name, old, value.clone());
+        }
+
+        match *slot {
+            Some(ref mut binding) if binding.depth == depth => {
+                if self.journal.is_recording() {
+                    let previous = Some(binding.clone());
+                    self.journal.record(Undo::Slot(symbol, previous, false));
+                }
+
+                binding.value = value;
+                binding.constant = constant;
+            }
+            _ => {
+                let shadowed = slot.take();
+                if self.journal.is_recording() {
+                    self.journal
+                        .record(Undo::Slot(symbol, shadowed.clone(), true));
+                }
+
+                self.scopes[depth].push((symbol, shadowed));
+                *slot = Some(SlotBinding {
+                    value,
+                    constant,
+                    depth,
+                });
+            }
+        }
+        true
+    }
+
+    /// Sets the current binding of a symbol to a value
+    /// returns true if the symbol is bound and not read-only and false otherwise
+    pub fn assign_slot(&mut self, symbol: Symbol, value: Lit) -> bool {
+        match self.slots.get_mut(symbol.index()) {
+            Some(Some(binding)) if !binding.constant => {
+                if self.journal.is_recording() {
+                    let previous = Some(binding.clone());
+                    self.journal.record(Undo::Slot(symbol, previous, false));
+                }
+
+                if let Some(log) = self.changes.as_mut()
+                    && let Some(name) = self.interner.resolve(symbol)
+                {
+                    log.record(name, Some(binding.value.clone()), value.clone());
+                }
+
+                binding.value = value;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for SlotEnvironment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Environment for SlotEnvironment {
+    fn get(&self, var: &str) -> Option<Lit> {
+        self.get_ref(var).cloned()
+    }
+
+    fn contains(&self, var: &str) -> bool {
+        self.get_ref(var).is_some()
+    }
+
+    fn assign(&mut self, var: &str, value: Lit) -> bool {
+        match self.interner.lookup(var) {
+            Some(symbol) => self.assign_slot(symbol, value),
+            None => false,
+        }
+    }
+
+    fn set(&mut self, var: &str, value: Lit) {
+        let symbol = self.symbol(var);
+        self.set_slot(symbol, value, false);
+    }
+
+    fn set_const(&mut self, var: &str, value: Lit) {
+        let symbol = self.symbol(var);
+        self.set_slot(symbol, value, true);
+    }
+
+    fn is_const(&self, var: &str) -> bool {
+        match self.interner.lookup(var) {
+            Some(symbol) => self.is_const_slot(symbol),
+            None => false,
+        }
+    }
+
+    fn is_const_in_scope(&self, var: &str) -> bool {
+        match self.interner.lookup(var) {
+            Some(symbol) => self.is_const_in_scope_slot(symbol),
+            None => false,
+        }
+    }
+
+    fn remove(&mut self, var: &str) -> Option<Lit> {
+        let symbol = self.interner.lookup(var)?;
+        let depth = self.slots.get(symbol.index())?.as_ref()?.depth;
+
+        // the binding's entry in its scope holds the binding it shadowed
+        // (found before changing anything so a failed lookup leaves the slot as it was)
+        let scope = &mut self.scopes[depth];
+        let position = scope.iter().rposition(|&(s, _)| s == symbol)?;
+        let (_, shadowed) = scope.remove(position);
+        let binding = std::mem::replace(&mut self.slots[symbol.index()], shadowed.clone())?;
+
+        let value = binding.value.clone();
+        self.journal
+            .record(Undo::Remove(symbol, binding, position, shadowed));
+        Some(value)
+    }
+
+    fn bindings(&self) -> Vec<BindingInfo> {
+        let mut bindings = self
+            .interner
+            .iter()
+            .filter_map(|(symbol, name)| {
+                let binding = self.slots.get(symbol.index())?.as_ref()?;
+                Some(BindingInfo {
+                    name: name.to_owned(),
+                    lit_type: binding.value.lit_type(),
+                    depth: binding.depth,
+                    constant: binding.constant,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        bindings.sort_by(|a, b| a.name.cmp(&b.name));
+        bindings
+    }
+
+    fn push(&mut self) {
+        self.scopes.push(Vec::new());
+        self.journal.record(Undo::Push);
+    }
+
+    fn pop(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            if self.journal.is_recording() {
+                let mut replaced = Vec::new();
+                for (symbol, shadowed) in scope.iter().rev() {
+                    let slot = &mut self.slots[symbol.index()];
+                    replaced.push(std::mem::replace(slot, shadowed.clone()));
+                }
+                self.journal.record(Undo::Pop(scope, replaced));
+            } else {
+                for (symbol, shadowed) in scope.into_iter().rev() {
+                    self.slots[symbol.index()] = shadowed;
+                }
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.scopes.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+
+    fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    fn snapshot(&mut self) -> Snapshot {
+        self.journal.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) -> bool {
+        let undos = match self.journal.rollback(snapshot) {
+            Some(undos) => undos,
+            None => return false,
+        };
+
+        for undo in undos {
+            match undo {
+                Undo::Slot(symbol, previous, shadowed) => {
+                    self.slots[symbol.index()] = previous;
+                    if shadowed && let Some(scope) = self.scopes.last_mut() {
+                        scope.pop();
+                    }
+                }
+                Undo::Push => {
+                    self.scopes.pop();
+                }
+                Undo::Pop(scope, replaced) => {
+                    // each symbol is shadowed at most once in a scope
+                    // so the slots can be put back in any order
+                    for ((symbol, _), binding) in scope.iter().rev().zip(replaced) {
+                        self.slots[symbol.index()] = binding;
+                    }
+                    self.scopes.push(scope);
+                }
+                Undo::Remove(symbol, binding, position, shadowed) => {
+                    self.scopes[binding.depth].insert(position, (symbol, shadowed));
+                    self.slots[symbol.index()] = Some(binding);
+                }
+            }
+        }
+        true
+    }
+
+    fn commit(&mut self, snapshot: Snapshot) -> bool {
+        self.journal.commit(snapshot)
+    }
+
+    fn change_log(&self) -> Option<&ChangeLog> {
+        self.changes.as_ref()
+    }
+
+    fn change_log_mut(&mut self) -> Option<&mut ChangeLog> {
+        self.changes.as_mut()
+    }
+}
+
+// the checks look variables up by symbol and only resolve
+// the name of a symbol when reporting an error
+impl crate::ast::exp::Bindings<Symbol> for SlotEnvironment {
+    fn name(&self, var: Symbol) -> String {
+        SlotEnvironment::name(self, var).map_or_else(|| format!("{:?}", var), str::to_owned)
+    }
+
+    fn is_const(&self, var: Symbol) -> bool {
+        self.is_const_slot(var)
+    }
+
+    fn is_const_in_scope(&self, var: Symbol) -> bool {
+        self.is_const_in_scope_slot(var)
+    }
+
+    fn bind(&mut self, var: Symbol, value: Lit, constant: bool) -> bool {
+        self.set_slot(var, value, constant)
+    }
+
+    fn assign(&mut self, var: Symbol, value: Lit) -> bool {
+        self.assign_slot(var, value)
+    }
+
+    fn is_lenient(&self) -> bool {
+        Environment::is_lenient(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_value() {
+        let mut env = SlotEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.push();
+        env.set("hello", Lit::Number(3));
+        env.set("hello", Lit::Number(4));
+        env.set("world", Lit::Number(5));
+
+        assert_eq!(env.get("hello"), Some(Lit::Number(4)));
+
+        env.pop();
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert_eq!(env.get("world"), None);
+        assert_eq!(env.len(), 1);
+    }
+
+    #[test]
+    fn test_assign_sets_value_in_other_scope() {
+        let mut env = SlotEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.set_const("season", Lit::Str("spring".to_owned()));
+        env.push();
+
+        assert!(env.assign("hello", Lit::Number(3)));
+        assert!(!env.assign("season", Lit::Str("winter".to_owned())));
+        assert!(!env.assign("world", Lit::Number(3)));
+        env.pop();
+
+        assert_eq!(env.get("hello"), Some(Lit::Number(3)));
+        assert_eq!(env.get_ref("season"), Some(&Lit::Str("spring".to_owned())));
+        assert!(env.is_const("season"));
+    }
+
+    #[test]
+    fn test_remove_and_restore() {
+        let mut env = SlotEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.push();
+        env.set("world", Lit::Bool(true));
+        env.set("hello", Lit::Number(3));
+
+        let snapshot = env.snapshot();
+        assert_eq!(env.remove("hello"), Some(Lit::Number(3)));
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert_eq!(env.bindings().len(), 2);
+        assert_eq!(env.bindings()[0].depth, 0);
+
+        assert!(env.restore(snapshot));
+        assert_eq!(env.get("hello"), Some(Lit::Number(3)));
+
+        // popping the scope still reveals the outer binding
+        env.pop();
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert!(!env.contains("world"));
+    }
+
+    #[test]
+    fn test_restore_snapshot() {
+        let mut env = SlotEnvironment::new();
+        env.set("hello", Lit::Number(2));
+        env.push();
+        env.set("hello", Lit::Number(3));
+
+        let snapshot = env.snapshot();
+        env.set("hello", Lit::Number(4));
+        env.pop();
+        env.assign("hello", Lit::Number(5));
+        env.push();
+        env.set("world", Lit::Number(6));
+
+        assert!(env.restore(snapshot));
+        assert_eq!(env.len(), 2);
+        assert_eq!(env.get("hello"), Some(Lit::Number(3)));
+        assert_eq!(env.get("world"), None);
+
+        env.pop();
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+    }
+
+    #[test]
+    fn test_slots() {
+        let mut env = SlotEnvironment::new();
+        let hello = env.symbol("hello");
+        assert_eq!(env.get_slot(hello), None);
+
+        assert!(env.set_slot(hello, Lit::Number(1), false));
+        assert!(env.assign_slot(hello, Lit::Number(2)));
+        assert_eq!(env.get_slot(hello), Some(&Lit::Number(2)));
+        assert_eq!(env.get("hello"), Some(Lit::Number(2)));
+        assert_eq!(env.name(hello), Some("hello"));
+
+        // a symbol of another environment is not bound here
+        let mut other = SlotEnvironment::new();
+        other.symbol("a");
+        let foreign = other.symbol("b");
+        assert!(!env.set_slot(foreign, Lit::Number(3), false));
+        assert!(!env.assign_slot(foreign, Lit::Number(3)));
+        assert_eq!(env.get_slot(foreign), None);
+        assert_eq!(env.name(foreign), None);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::ast::{Exp, Lit, Op};
+use crate::errors::YamlError;
+use crate::parser::compile;
+
+/// A single stack machine instruction
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Instr {
+    /// Pushes the constant at the index onto the stack
+    Const(u32),
+    /// Pushes the value of the variable in the slot onto the stack
+    Load(u32),
+    /// Binds the variable in the slot to the value on top of the stack
+    Declare(u32),
+    /// Binds the read-only variable in the slot to the value on top of the stack
+    DeclareConst(u32),
+    /// Sets the existing variable in the slot to the value on top of the stack
+    Assign(u32),
+    /// Replaces the value on top of the stack with the operator applied to it
+    Unary(Op),
+    /// Replaces the two values on top of the stack with the operator applied to them
+    Binary(Op),
+}
+
+/// An expression compiled to bytecode with its constants and
+/// the variable names referred to by each slot index
+#[derive(Debug, PartialEq, Clone)]
+pub struct Chunk {
+    pub(crate) code: Vec<Instr>,
+    pub(crate) constants: Vec<Lit>,
+    pub(crate) names: Vec<String>,
+}
+
+impl Chunk {
+    /// Compiles an expression string (without the "~>" prefix) to bytecode
+    pub fn compile(s: &str) -> Result<Chunk, YamlError> {
+        Ok(Chunk::from_exp(&compile(s)?))
+    }
+
+    /// Compiles an AST to bytecode
+    pub fn from_exp(exp: &Exp) -> Chunk {
+        let mut chunk = Chunk {
+            code: Vec::new(),
+            constants: Vec::new(),
+            names: Vec::new(),
+        };
+        chunk.emit(exp);
+        chunk
+    }
+
+    /// Returns the instructions of the chunk
+    pub fn code(&self) -> &[Instr] {
+        &self.code
+    }
+
+    /// Returns the variable names in slot order
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    // returns the slot for a variable name, adding it if it is new
+    fn slot(&mut self, name: &str) -> u32 {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => i as u32,
+            None => {
+                self.names.push(name.to_owned());
+                (self.names.len() - 1) as u32
+            }
+        }
+    }
+
+    fn emit(&mut self, exp: &Exp) {
+        match *exp {
+            Exp::Lit(ref lit) => {
+                self.constants.push(lit.clone());
+                self.code
+                    .push(Instr::Const((self.constants.len() - 1) as u32));
+            }
+            Exp::Variable(ref name) => {
+                let slot = self.slot(name);
+                self.code.push(Instr::Load(slot));
+            }
+            Exp::Declare(ref name, ref exp) => {
+                self.emit(exp);
+                let slot = self.slot(name);
+                self.code.push(Instr::Declare(slot));
+            }
+            Exp::DeclareConst(ref name, ref exp) => {
+                self.emit(exp);
+                let slot = self.slot(name);
+                self.code.push(Instr::DeclareConst(slot));
+            }
+            Exp::Assign(ref name, ref exp) => {
+                self.emit(exp);
+                let slot = self.slot(name);
+                self.code.push(Instr::Assign(slot));
+            }
+            Exp::UnaryOp(op, ref exp) => {
+                self.emit(exp);
+                self.code.push(Instr::Unary(op));
+            }
+            Exp::BinaryOp(op, ref exp1, ref exp2) => {
+                self.emit(exp1);
+                self.emit(exp2);
+                self.code.push(Instr::Binary(op));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_slots() {
+        // Test that `a = a * (b + 3)` reuses the slot for a
+        // and that the constant sub-tree is folded
+        let chunk = Chunk::compile("a = a * (b + 3 * 2)").unwrap();
+
+        assert_eq!(chunk.names(), &["a".to_owned(), "b".to_owned()]);
+        assert_eq!(
+            chunk.code(),
+            &[
+                Instr::Load(0),
+                Instr::Load(1),
+                Instr::Const(0),
+                Instr::Binary(Op::Plus),
+                Instr::Binary(Op::Times),
+                Instr::Assign(0),
+            ]
+        );
+        assert_eq!(chunk.constants, vec![Lit::Number(6)]);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::ast::Lit;
+use crate::ast::exp::{assign, assign_var, declare, declare_var};
+use crate::environment::Environment;
+use crate::errors::{EvalError, YamlError};
+use crate::interner::Symbol;
+use crate::slot_environment::SlotEnvironment;
+use crate::vm::chunk::{Chunk, Instr};
+use crate::yaml::ExpRunner;
+use std::collections::HashMap;
+
+/// A stack machine that runs compiled chunks
+///
+/// The stack is kept between runs so evaluating in a loop does not allocate,
+/// and when used as an ExpRunner the chunk for each expression
+/// string is compiled once and cached
+pub struct Vm {
+    stack: Vec<Lit>,
+    chunks: HashMap<String, Chunk>,
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm {
+            stack: Vec::new(),
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Runs a chunk and returns the resulting value
+    pub fn run(&mut self, chunk: &Chunk, env: &mut impl Environment) -> Result<Lit, YamlError> {
+        let mut variables = Named {
+            env,
+            names: &chunk.names,
+        };
+        execute(&mut self.stack, chunk, &mut variables)
+    }
+
+    /// Runs a chunk with its variable slots already resolved to symbols
+    /// (from SlotEnvironment::bind) so variables are accessed without hashing
+    pub fn run_bound(
+        &mut self,
+        chunk: &Chunk,
+        symbols: &[Symbol],
+        env: &mut SlotEnvironment,
+    ) -> Result<Lit, YamlError> {
+        assert_eq!(symbols.len(), chunk.names.len(), "chunk is not bound");
+
+        let mut variables = Bound { env, symbols };
+        execute(&mut self.stack, chunk, &mut variables)
+    }
+}
+
+/// How the machine accesses the variables in the slots of a chunk
+trait Variables {
+    fn load(&self, slot: u32) -> Result<Lit, YamlError>;
+    fn declare(&mut self, slot: u32, value: Lit, constant: bool) -> Result<(), YamlError>;
+    fn assign(&mut self, slot: u32, value: Lit) -> Result<(), YamlError>;
+}
+
+/// Accesses variables in any environment by their names
+struct Named<'a, E> {
+    env: &'a mut E,
+    names: &'a [String],
+}
+
+impl<E: Environment> Variables for Named<'_, E> {
+    fn load(&self, slot: u32) -> Result<Lit, YamlError> {
+        let name = &self.names[slot as usize];
+        match self.env.get(name) {
+            Some(value) => Ok(value),
+            None => Err(YamlError::EvalError(EvalError::VarNotInEnv(name.clone()))),
+        }
+    }
+
+    fn declare(&mut self, slot: u32, value: Lit, constant: bool) -> Result<(), YamlError> {
+        declare(self.env, &self.names[slot as usize], value, constant)
+    }
+
+    fn assign(&mut self, slot: u32, value: Lit) -> Result<(), YamlError> {
+        assign(self.env, &self.names[slot as usize], value)
+    }
+}
+
+/// Accesses variables in a slot environment by their symbols
+struct Bound<'a> {
+    env: &'a mut SlotEnvironment,
+    symbols: &'a [Symbol],
+}
+
+impl Variables for Bound<'_> {
+    fn load(&self, slot: u32) -> Result<Lit, YamlError> {
+        let symbol = self.symbols[slot as usize];
+        match self.env.get_slot(symbol) {
+            Some(value) => Ok(value.clone()),
+            None => Err(YamlError::EvalError(EvalError::VarNotInEnv(
+                crate::ast::exp::Bindings::name(&*self.env, symbol),
+            ))),
+        }
+    }
+
+    fn declare(&mut self, slot: u32, value: Lit, constant: bool) -> Result<(), YamlError> {
+        declare_var(self.env, self.symbols[slot as usize], value, constant)
+    }
+
+    fn assign(&mut self, slot: u32, value: Lit) -> Result<(), YamlError> {
+        assign_var(self.env, self.symbols[slot as usize], value)
+    }
+}
+
+// chunks are only built from well formed ASTs so every
+// instruction has the operands it needs on the stack
+fn pop(stack: &mut Vec<Lit>) -> Lit {
+    stack.pop().expect("vm stack underflow")
+}
+
+fn top(stack: &[Lit]) -> Lit {
+    stack.last().cloned().expect("vm stack underflow")
+}
+
+fn execute(
+    stack: &mut Vec<Lit>,
+    chunk: &Chunk,
+    variables: &mut impl Variables,
+) -> Result<Lit, YamlError> {
+    stack.clear();
+
+    for instr in &chunk.code {
+        match *instr {
+            Instr::Const(i) => stack.push(chunk.constants[i as usize].clone()),
+            Instr::Load(slot) => stack.push(variables.load(slot)?),
+            Instr::Declare(slot) => variables.declare(slot, top(stack), false)?,
+            Instr::DeclareConst(slot) => variables.declare(slot, top(stack), true)?,
+            Instr::Assign(slot) => variables.assign(slot, top(stack))?,
+            Instr::Unary(op) => {
+                let value = pop(stack);
+                stack.push(value.apply_unary(op)?);
+            }
+            Instr::Binary(op) => {
+                let value2 = pop(stack);
+                let value1 = pop(stack);
+                stack.push(value1.apply_binary(op, value2)?);
+            }
+        }
+    }
+
+    Ok(pop(stack))
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExpRunner for Vm {
+    fn run(&mut self, s: &str, env: &mut impl Environment) -> Result<Lit, YamlError> {
+        if !self.chunks.contains_key(s) {
+            self.chunks.insert(s.to_owned(), Chunk::compile(s)?);
+        }
+
+        let chunk = &self.chunks[s];
+        let mut variables = Named {
+            env,
+            names: &chunk.names,
+        };
+        execute(&mut self.stack, chunk, &mut variables)
+    }
+}
+
+/// Runs expressions with chunks compiled ahead of time (by a Program)
+/// and compiles the expressions that were not without caching them
+pub(crate) struct SharedRunner<'a> {
+    stack: Vec<Lit>,
+    chunks: &'a HashMap<String, Chunk>,
+}
+
+impl SharedRunner<'_> {
+    pub fn new(chunks: &HashMap<String, Chunk>) -> SharedRunner<'_> {
+        SharedRunner {
+            stack: Vec::new(),
+            chunks,
+        }
+    }
+}
+
+impl ExpRunner for SharedRunner<'_> {
+    fn run(&mut self, s: &str, env: &mut impl Environment) -> Result<Lit, YamlError> {
+        let compiled;
+        let chunk = match self.chunks.get(s) {
+            Some(chunk) => chunk,
+            None => {
+                compiled = Chunk::compile(s)?;
+                &compiled
+            }
+        };
+
+        let mut variables = Named {
+            env,
+            names: &chunk.names,
+        };
+        execute(&mut self.stack, chunk, &mut variables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::ASTEnvironment;
+    use crate::yaml::{evaluate, evaluate_with};
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_run_matches_tree_walker() {
+        let sources = [
+            "a * (b - c + d)",
+            "1.5 - b + c",
+            "!(a == 5) || b != 3",
+            "x := a * (2 + 3)",
+            "\"Hello\" + \" world\"",
+        ];
+
+        for source in sources.iter() {
+            let mut env = ASTEnvironment::new();
+            env.set("a", Lit::Number(5));
+            env.set("b", Lit::Number(3));
+            env.set("c", Lit::Number(2));
+            env.set("d", Lit::Number(6));
+            let expected = crate::parser::compile(source).unwrap().eval(&mut env);
+
+            let mut env = ASTEnvironment::new();
+            env.set("a", Lit::Number(5));
+            env.set("b", Lit::Number(3));
+            env.set("c", Lit::Number(2));
+            env.set("d", Lit::Number(6));
+            let chunk = Chunk::compile(source).unwrap();
+            let result = Vm::new().run(&chunk, &mut env);
+
+            assert_eq!(result.map(crate::ast::Exp::Lit), expected);
+        }
+    }
+
+    #[test]
+    fn test_run_errors() {
+        let mut env = ASTEnvironment::new();
+        env.set_const("season", Lit::Str("spring".to_owned()));
+        let mut vm = Vm::new();
+
+        let chunk = Chunk::compile("season = \"winter\"").unwrap();
+        assert_eq!(
+            vm.run(&chunk, &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "season".to_owned()
+            )))
+        );
+
+        let chunk = Chunk::compile("missing + 1").unwrap();
+        assert_eq!(
+            vm.run(&chunk, &mut env),
+            Err(YamlError::EvalError(EvalError::VarNotInEnv(
+                "missing".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_run_bound() {
+        let chunk = Chunk::compile("total = total + i * (2 + 3)").unwrap();
+        let mut env = SlotEnvironment::new();
+        env.set("total", Lit::Number(0));
+        env.set("i", Lit::Number(2));
+
+        let symbols = env.bind(&chunk);
+        let mut vm = Vm::new();
+        for _ in 0..3 {
+            vm.run_bound(&chunk, &symbols, &mut env).unwrap();
+        }
+        assert_eq!(env.get("total"), Some(Lit::Number(30)));
+
+        let chunk = Chunk::compile("missing = 1").unwrap();
+        let symbols = env.bind(&chunk);
+        assert_eq!(
+            vm.run_bound(&chunk, &symbols, &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToUndeclared(
+                "missing".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_evaluate_document() {
+        let s = "
+        foo:
+          - '~> a := 0'
+          - while:
+            - '~> a != 5'
+            - do:
+              - '~> a = a + 1'
+          - '~> a * (2 + 3)'
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+
+        let mut env = ASTEnvironment::new();
+        let expected = evaluate(&docs[0]["foo"], &mut env);
+
+        let mut env = ASTEnvironment::new();
+        let mut vm = Vm::new();
+        assert_eq!(evaluate_with(&docs[0]["foo"], &mut env, &mut vm), expected);
+        assert_eq!(env.get("a"), Some(Lit::Number(5)));
+    }
+}
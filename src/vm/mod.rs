@@ -0,0 +1,7 @@
+mod chunk;
+mod machine;
+mod program;
+
+pub use crate::vm::chunk::{Chunk, Instr};
+pub use crate::vm::machine::Vm;
+pub use crate::vm::program::Program;
@@ -0,0 +1,210 @@
+use crate::environment::Environment;
+use crate::errors::YamlError;
+use crate::vm::chunk::Chunk;
+use crate::vm::machine::SharedRunner;
+use crate::yaml::{evaluate_with, expressions};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::num::NonZeroUsize;
+use std::thread;
+use yaml_rust::yaml::Yaml;
+
+/// A YAML document with every expression in it compiled ahead of time
+///
+/// A program is never modified after it is compiled so it is Sync: it can be
+/// shared between threads (by reference or in an Arc) with each thread
+/// evaluating it in its own environment
+pub struct Program {
+    yaml: Yaml,
+    chunks: HashMap<String, Chunk>,
+    errors: Vec<(String, YamlError)>,
+}
+
+impl Program {
+    /// Compiles every expression in the YAML
+    ///
+    /// An expression that does not compile is not an error until it is evaluated
+    /// (like with yaml::evaluate, so an expression in a branch that does not run
+    /// cannot fail the document), the errors are kept to be checked with errors
+    pub fn compile(yaml: Yaml) -> Program {
+        let mut chunks = HashMap::new();
+        let mut errors = Vec::new();
+        for exp in expressions(&yaml) {
+            if let Entry::Vacant(entry) = chunks.entry(exp) {
+                match Chunk::compile(entry.key()) {
+                    Ok(chunk) => {
+                        entry.insert(chunk);
+                    }
+                    Err(err) => errors.push((entry.into_key(), err)),
+                }
+            }
+        }
+
+        Program {
+            yaml,
+            chunks,
+            errors,
+        }
+    }
+
+    pub fn yaml(&self) -> &Yaml {
+        &self.yaml
+    }
+
+    /// Returns the expressions that did not compile with their errors
+    pub fn errors(&self) -> &[(String, YamlError)] {
+        &self.errors
+    }
+
+    /// Evaluates the YAML of the program like yaml::evaluate
+    pub fn evaluate(&self, env: &mut impl Environment) -> Result<Yaml, YamlError> {
+        evaluate_with(&self.yaml, env, &mut SharedRunner::new(&self.chunks))
+    }
+
+    /// Evaluates the program once in every environment, splitting the
+    /// environments between threads, and returns the results in the same order
+    pub fn evaluate_all<E: Environment + Send>(
+        &self,
+        envs: &mut [E],
+    ) -> Vec<Result<Yaml, YamlError>> {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let size = envs.len().div_ceil(threads).max(1);
+
+        thread::scope(|scope| {
+            let handles = envs
+                .chunks_mut(size)
+                .map(|envs| {
+                    scope.spawn(move || {
+                        envs.iter_mut()
+                            .map(|env| self.evaluate(env))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Exp, Lit};
+    use crate::environment::ASTEnvironment;
+    use crate::layered_environment::LayeredEnvironment;
+    use crate::slot_environment::SlotEnvironment;
+    use crate::vm::Vm;
+    use crate::yaml::evaluate;
+    use std::sync::Arc;
+    use yaml_rust::YamlLoader;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn test_thread_safety() {
+        assert_send::<Yaml>();
+        assert_sync::<Yaml>();
+        assert_send::<Program>();
+        assert_sync::<Program>();
+        assert_send::<Chunk>();
+        assert_sync::<Chunk>();
+        assert_send::<Exp>();
+        assert_sync::<Exp>();
+        assert_send::<Vm>();
+        assert_send::<YamlError>();
+        assert_send::<ASTEnvironment>();
+        assert_sync::<ASTEnvironment>();
+        assert_send::<SlotEnvironment>();
+        assert_send::<LayeredEnvironment>();
+    }
+
+    #[test]
+    fn test_compile_conditions() {
+        let s = "
+        - '~> a := 0'
+        - while:
+          - '~> a != 5'
+          - do:
+            - '~> a = a + 1'
+        - '~> a'
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let program = Program::compile(docs[0].clone());
+        assert!(program.chunks.contains_key(" a := 0"));
+        assert!(program.chunks.contains_key(" ( a != 5)"));
+
+        assert!(program.errors().is_empty());
+    }
+
+    #[test]
+    fn test_errors_when_reached() {
+        let s = "
+        foo:
+          - if:
+            - '~> a == 1'
+            - do:
+              - '~> b := +'
+              - '~> b / 0'
+              else: blue
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let program = Program::compile(docs[0]["foo"].clone());
+        assert_eq!(program.errors().len(), 1);
+        assert_eq!(program.errors()[0].0, " b := +");
+
+        // the expression that does not compile is in a branch that does not run
+        let mut env = ASTEnvironment::new();
+        env.set("a", Lit::Number(0));
+        assert_eq!(
+            program.evaluate(&mut env),
+            Ok(Yaml::String("blue".to_owned()))
+        );
+
+        // and fails like it does with evaluate when it runs
+        env.set("a", Lit::Number(1));
+        assert_eq!(
+            program.evaluate(&mut env),
+            evaluate(&docs[0]["foo"], &mut env)
+        );
+        assert!(program.evaluate(&mut env).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_all() {
+        let s = "
+        if:
+          - '~> nearby == 1'
+          - do:
+            - '~> nearby = 0'
+            - '~> season'
+            else: blue
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let program = Program::compile(docs[0].clone());
+
+        let mut globals = ASTEnvironment::new();
+        globals.set("season", Lit::Str("spring".to_owned()));
+        let globals = Arc::new(globals);
+
+        let mut envs = (0..20)
+            .map(|i| {
+                let mut env = LayeredEnvironment::new(globals.clone());
+                env.set("nearby", Lit::Number(i % 2));
+                env
+            })
+            .collect::<Vec<_>>();
+
+        let results = program.evaluate_all(&mut envs);
+        assert_eq!(results.len(), 20);
+        for (i, (result, env)) in results.into_iter().zip(&envs).enumerate() {
+            let expected = if i % 2 == 1 { "spring" } else { "blue" };
+            assert_eq!(result, Ok(Yaml::String(expected.to_owned())));
+            assert_eq!(env.get("nearby"), Some(Lit::Number(0)));
+        }
+    }
+}
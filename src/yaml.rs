@@ -1,8 +1,9 @@
 use crate::ast::{Exp, Lit};
 use crate::environment::Environment;
-use crate::errors::YamlError;
-use crate::lexer::Lexer;
-use crate::parser::Parser;
+use crate::errors::{EvalError, YamlError};
+use crate::parser::compile;
+use crate::path::get_path;
+use crate::vm::Vm;
 use linked_hash_map::LinkedHashMap;
 use yaml_rust::yaml::Yaml;
 
@@ -12,27 +13,103 @@ pub enum YamlType {
     Return(Yaml),
 }
 
+/// Runs the expressions (the part of a string after "~>") found in YAML
+pub trait ExpRunner {
+    /// Runs an expression and returns the resulting value
+    fn run(&mut self, s: &str, env: &mut impl Environment) -> Result<Lit, YamlError>;
+
+    /// Returns true if a function with the name can be called
+    /// (a hash with the name as its key calls the function)
+    fn is_function(&self, _name: &str) -> bool {
+        false
+    }
+
+    /// Calls a function with the values of its arguments
+    fn call(&mut self, name: &str, _args: &[Lit]) -> Result<Lit, YamlError> {
+        Err(YamlError::EvalError(EvalError::FunctionNotFound(
+            name.to_owned(),
+        )))
+    }
+
+    /// The most times a while loop can run, None if there is no limit
+    fn max_iterations(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Runs expressions by compiling them and walking the AST every time
+pub struct TreeWalker;
+
+impl ExpRunner for TreeWalker {
+    fn run(&mut self, s: &str, env: &mut impl Environment) -> Result<Lit, YamlError> {
+        match compile(s)?.eval(env)? {
+            Exp::Lit(lit) => Ok(lit),
+            _ => Ok(Lit::Nil),
+        }
+    }
+}
+
+impl From<Lit> for Yaml {
+    fn from(lit: Lit) -> Yaml {
+        match lit {
+            Lit::Decimal(d) => Yaml::Real(d.to_string()),
+            Lit::Number(n) => Yaml::Integer(n as i64),
+            Lit::Bool(b) => Yaml::Boolean(b),
+            Lit::Str(s) => Yaml::String(s),
+            Lit::Nil => Yaml::Null,
+        }
+    }
+}
+
+// runs an expression attributing the changes it makes to it
+// if the environment is tracking changes
+fn run_expression(
+    s: &str,
+    env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
+) -> Result<Lit, YamlError> {
+    if let Some(log) = env.change_log_mut() {
+        log.set_expression(Some(s));
+    }
+
+    let result = runner.run(s, env);
+
+    if let Some(log) = env.change_log_mut() {
+        log.set_expression(None);
+    }
+    result
+}
+
 // same as apply_keywords but only works on nested keywords in while statements
 fn apply_nested_while_keywords(
     h: &LinkedHashMap<Yaml, Yaml>,
     prop_str: &str,
     env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
 ) -> Result<YamlType, YamlError> {
     for (key, val) in h {
         if let Yaml::String(ref keyword) = *key
             && keyword.as_str() == "do"
         {
+            let mut iterations = 0;
             loop {
                 // check proposition if true
-                let result = evaluate_helper(&Yaml::String(prop_str.to_owned()), env)?;
+                let result = evaluate_helper(&Yaml::String(prop_str.to_owned()), env, runner)?;
                 if result == YamlType::Value(Yaml::Boolean(false)) {
                     break;
                 }
 
+                iterations += 1;
+                if let Some(limit) = runner.max_iterations()
+                    && iterations > limit
+                {
+                    return Err(YamlError::EvalError(EvalError::IterationLimit(limit)));
+                }
+
                 env.push();
 
                 // evaluate commands inside do block
-                evaluate_helper(val, env)?;
+                evaluate_helper(val, env, runner)?;
 
                 env.pop();
             }
@@ -48,27 +125,24 @@ fn apply_nested_if_keywords(
     h: &LinkedHashMap<Yaml, Yaml>,
     prop_str: &str,
     env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
 ) -> Result<YamlType, YamlError> {
     for (key, val) in h {
         if let Yaml::String(ref keyword) = *key {
-            let result = evaluate_helper(&Yaml::String(prop_str.to_owned()), env)?;
+            let result = evaluate_helper(&Yaml::String(prop_str.to_owned()), env, runner)?;
 
             match keyword.as_str() {
-                "do" => {
-                    if result == YamlType::Value(Yaml::Boolean(true)) {
-                        env.push();
-                        let result = evaluate_helper(val, env)?;
-                        env.pop();
-                        return Ok(result);
-                    }
+                "do" if result == YamlType::Value(Yaml::Boolean(true)) => {
+                    env.push();
+                    let result = evaluate_helper(val, env, runner)?;
+                    env.pop();
+                    return Ok(result);
                 }
-                "else" => {
-                    if result == YamlType::Value(Yaml::Boolean(false)) {
-                        env.push();
-                        let result = evaluate_helper(val, env)?;
-                        env.pop();
-                        return Ok(result);
-                    }
+                "else" if result == YamlType::Value(Yaml::Boolean(false)) => {
+                    env.push();
+                    let result = evaluate_helper(val, env, runner)?;
+                    env.pop();
+                    return Ok(result);
                 }
                 _ => {}
             }
@@ -78,44 +152,82 @@ fn apply_nested_if_keywords(
     Ok(YamlType::Value(Yaml::Hash(h.clone())))
 }
 
+// Builds main propositional logic of an if or while statement by anding the
+// logic statements in the list before the nested keywords together
+fn condition(arr: &[Yaml]) -> String {
+    let mut prop_str = String::new();
+    for val in arr {
+        match *val {
+            Yaml::String(ref s) if s.as_str().contains("~>") => {
+                let split_vec = s.as_str().split("~>").collect::<Vec<_>>();
+                let prop = split_vec[1];
+
+                if prop_str.is_empty() {
+                    prop_str = format!("~> ({})", prop);
+                } else {
+                    prop_str = format!("{} && ({})", prop_str, prop);
+                }
+            }
+            Yaml::Hash(_) => break,
+            _ => {}
+        }
+    }
+    prop_str
+}
+
+/// Returns every expression that evaluating the YAML (or any part of it) can run
+/// including the combined conditions of if and while statements
+pub(crate) fn expressions(yaml: &Yaml) -> Vec<String> {
+    let mut exps = Vec::new();
+    collect_expressions(yaml, &mut exps);
+    exps
+}
+
+fn collect_expressions(yaml: &Yaml, exps: &mut Vec<String>) {
+    match *yaml {
+        Yaml::String(ref s) if s.as_str().contains("~>") => {
+            exps.push(s.as_str().split("~>").collect::<Vec<_>>()[1].to_owned());
+        }
+        Yaml::Array(ref arr) => {
+            for v in arr {
+                collect_expressions(v, exps);
+            }
+        }
+        Yaml::Hash(ref h) => {
+            for (k, v) in h {
+                if let (Yaml::String(keyword), Yaml::Array(arr)) = (k, v)
+                    && (keyword == "if" || keyword == "while")
+                {
+                    collect_expressions(&Yaml::String(condition(arr)), exps);
+                }
+                collect_expressions(v, exps);
+            }
+        }
+        _ => {}
+    }
+}
+
 // applies the effects of keywords in a YAML hash
 fn apply_keyword(
     s: &str,
     _k: &Yaml,
     v: &Yaml,
     env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
 ) -> Result<YamlType, YamlError> {
     match s {
         "while" | "if" => {
             if let Yaml::Array(ref arr) = *v {
-                let mut prop_str = String::new();
+                let prop_str = condition(arr);
                 for val in arr {
-                    // Builds main propositional logic by anding the logic statements in the list
-                    // together
-                    if let Yaml::String(ref s) = *val {
-                        if s.as_str().contains("~>") {
-                            let split_vec = s.as_str().split("~>").collect::<Vec<_>>();
-                            let prop = split_vec[1];
-
-                            let str_len = prop_str.len();
-                            if str_len == 0 {
-                                prop_str = format!("~> ({})", prop);
-                            } else {
-                                prop_str = format!("{} && ({})", prop_str, prop);
-                            }
-                        }
-                    } else if let Yaml::Hash(ref h) = *val {
+                    if let Yaml::Hash(ref h) = *val {
                         // applies logic based on the type of keyword
                         match s {
                             "if" => {
-                                return apply_nested_if_keywords(h, prop_str.clone().as_str(), env);
+                                return apply_nested_if_keywords(h, &prop_str, env, runner);
                             }
                             "while" => {
-                                return apply_nested_while_keywords(
-                                    h,
-                                    prop_str.clone().as_str(),
-                                    env,
-                                );
+                                return apply_nested_while_keywords(h, &prop_str, env, runner);
                             }
                             _ => {}
                         }
@@ -124,38 +236,65 @@ fn apply_keyword(
             }
         }
         "return" => {
-            let result = evaluate_helper(v, env)?;
+            let result = evaluate_helper(v, env, runner)?;
             if let YamlType::Value(val) = result {
                 return Ok(YamlType::Return(val));
             }
             return Ok(result);
         }
+        name if runner.is_function(name) => {
+            return call_function(name, v, env, runner);
+        }
         _ => {}
     }
 
     Ok(YamlType::Value(v.clone()))
 }
 
+// calls a function with the values of its arguments
+// (every item of a list or a single value)
+fn call_function(
+    name: &str,
+    v: &Yaml,
+    env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
+) -> Result<YamlType, YamlError> {
+    let args = match *v {
+        Yaml::Array(ref arr) => arr.as_slice(),
+        ref v => std::slice::from_ref(v),
+    };
+
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        let (YamlType::Value(value) | YamlType::Return(value)) = evaluate_helper(arg, env, runner)?;
+        match Lit::from_yaml(&value) {
+            Some(lit) => values.push(lit),
+            None => {
+                return Err(YamlError::EvalError(EvalError::InvalidArgument(
+                    name.to_owned(),
+                )));
+            }
+        }
+    }
+
+    Ok(YamlType::Value(runner.call(name, &values)?.into()))
+}
+
 // evaluates the result of a fragment of YAML
-fn evaluate_helper(yaml: &Yaml, env: &mut impl Environment) -> Result<YamlType, YamlError> {
+fn evaluate_helper(
+    yaml: &Yaml,
+    env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
+) -> Result<YamlType, YamlError> {
     match *yaml {
         Yaml::String(ref s) => {
             if s.as_str().contains("~>") {
                 let split_vec = s.as_str().split("~>").collect::<Vec<_>>();
-                let mut parser = Parser::new();
-
-                let mut lexer = Lexer::new();
-                lexer.parse_string(split_vec[1])?;
-                let ast =
-                    parser.parse_to_ast(&mut lexer.state.variables, &mut lexer.state.operators)?;
-                let result = ast.eval(env)?;
+                let result = run_expression(split_vec[1], env, runner)?;
 
                 Ok(YamlType::Value(match result {
-                    Exp::Lit(Lit::Decimal(d)) => Yaml::Real(d.to_string()),
-                    Exp::Lit(Lit::Number(n)) => Yaml::Integer(n as i64),
-                    Exp::Lit(Lit::Bool(b)) => Yaml::Boolean(b),
-                    Exp::Lit(Lit::Str(s)) => Yaml::String(s),
-                    _ => Yaml::String(split_vec[1].to_owned()),
+                    Lit::Nil => Yaml::String(split_vec[1].to_owned()),
+                    lit => lit.into(),
                 }))
             } else {
                 Ok(YamlType::Value(Yaml::String(s.clone())))
@@ -164,7 +303,7 @@ fn evaluate_helper(yaml: &Yaml, env: &mut impl Environment) -> Result<YamlType,
         Yaml::Array(ref arr) => {
             let mut last_value: Option<Yaml> = None;
             for v in arr {
-                let result = evaluate_helper(v, env)?;
+                let result = evaluate_helper(v, env, runner)?;
                 if let YamlType::Return(val) = result {
                     return Ok(YamlType::Return(val));
                 } else if let YamlType::Value(val) = result {
@@ -181,7 +320,7 @@ fn evaluate_helper(yaml: &Yaml, env: &mut impl Environment) -> Result<YamlType,
         Yaml::Hash(ref h) => {
             for (k, v) in h {
                 if let Yaml::String(ref s) = *k {
-                    return apply_keyword(s.as_str(), k, v, env);
+                    return apply_keyword(s.as_str(), k, v, env, runner);
                 }
             }
             Ok(YamlType::Value(Yaml::Hash(h.clone())))
@@ -192,7 +331,17 @@ fn evaluate_helper(yaml: &Yaml, env: &mut impl Environment) -> Result<YamlType,
 
 // Main function for evaluating YAML
 pub fn evaluate(yaml: &Yaml, env: &mut impl Environment) -> Result<Yaml, YamlError> {
-    let result = evaluate_helper(yaml, env)?;
+    evaluate_with(yaml, env, &mut TreeWalker)
+}
+
+/// Evaluates YAML using the given runner for expressions
+/// (like a vm::Vm that caches the compiled bytecode)
+pub fn evaluate_with(
+    yaml: &Yaml,
+    env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
+) -> Result<Yaml, YamlError> {
+    let result = evaluate_helper(yaml, env, runner)?;
 
     Ok(match result {
         YamlType::Value(v) => v,
@@ -200,11 +349,52 @@ pub fn evaluate(yaml: &Yaml, env: &mut impl Environment) -> Result<Yaml, YamlErr
     })
 }
 
+/// Evaluates the value at a path of the document ("cricket.wing_color", "bugs[3].name")
+/// without cloning the parts of the document leading to it
+pub fn evaluate_path(
+    yaml: &Yaml,
+    path: &str,
+    env: &mut impl Environment,
+) -> Result<Yaml, YamlError> {
+    evaluate_path_with(yaml, path, env, &mut TreeWalker)
+}
+
+pub(crate) fn evaluate_path_with(
+    yaml: &Yaml,
+    path: &str,
+    env: &mut impl Environment,
+    runner: &mut impl ExpRunner,
+) -> Result<Yaml, YamlError> {
+    match get_path(yaml, path).map_err(YamlError::PathError)? {
+        Some(value) => evaluate_with(value, env, runner),
+        None => Err(YamlError::EvalError(EvalError::PathNotFound(
+            path.to_owned(),
+        ))),
+    }
+}
+
+/// Evaluates the value at each path of the document in the environment paired with it
+/// and returns the results in the same order
+///
+/// All of the items are evaluated with the same Vm so each expression
+/// is compiled once for the whole batch
+pub fn evaluate_batch<'e, E: Environment + 'e>(
+    yaml: &Yaml,
+    items: impl IntoIterator<Item = (&'e str, &'e mut E)>,
+) -> Vec<Result<Yaml, YamlError>> {
+    let mut vm = Vm::new();
+    items
+        .into_iter()
+        .map(|(path, env)| evaluate_path_with(yaml, path, env, &mut vm))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ast::Lit;
-    use crate::environment::{ASTEnvironment, Environment};
+    use crate::environment::{ASTEnvironment, Change, Environment};
+    use crate::errors::EvalError;
     use yaml_rust::YamlLoader;
     use yaml_rust::yaml::Yaml;
 
@@ -310,6 +500,113 @@ mod tests {
         assert_eq!(env.get("c"), None);
     }
 
+    #[test]
+    fn test_assign_typo() {
+        // Test that a misspelled variable in an assignment is reported
+        // instead of silently leaving the state untouched
+        let s = "
+        foo:
+          - '~> another_beetle_neaby = 0'
+          - 'blue'
+        ";
+        let mut env = ASTEnvironment::new();
+        env.set("another_beetle_nearby", Lit::Number(1));
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert_eq!(
+            evaluate(&docs[0]["foo"], &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToUndeclared(
+                "another_beetle_neaby".to_owned()
+            )))
+        );
+
+        env.set_lenient(true);
+        assert_eq!(
+            evaluate(&docs[0]["foo"], &mut env),
+            Ok(Yaml::String("blue".to_owned()))
+        );
+        assert_eq!(env.get("another_beetle_nearby"), Some(Lit::Number(1)));
+    }
+
+    #[test]
+    fn test_const_declaration() {
+        // Test that host constants and const declarations cannot be changed
+        let s = "
+        foo:
+          - '~> const limit := 3'
+          - '~> limit = 4'
+        bar:
+          - '~> current_season = \"winter\"'
+        ";
+        let mut env = ASTEnvironment::new();
+        env.set_const("current_season", Lit::Str("spring".to_owned()));
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert_eq!(
+            evaluate(&docs[0]["foo"], &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "limit".to_owned()
+            )))
+        );
+        assert_eq!(env.get("limit"), Some(Lit::Number(3)));
+        assert_eq!(
+            evaluate(&docs[0]["bar"], &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "current_season".to_owned()
+            )))
+        );
+        assert_eq!(
+            env.get("current_season"),
+            Some(Lit::Str("spring".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_decimal_result() {
+        // Test that a whole real is returned without a fraction
+        // and a large one without an exponent
+        let s = "
+        foo: '~> 0.5 * 4'
+        bar: '~> 100000000000000000000.0 * 1'
+        ";
+        let mut env = ASTEnvironment::new();
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert_eq!(
+            evaluate(&docs[0]["foo"], &mut env),
+            Ok(Yaml::Real("2".to_owned()))
+        );
+        assert_eq!(
+            evaluate(&docs[0]["bar"], &mut env),
+            Ok(Yaml::Real("100000000000000000000".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_const_shadowing() {
+        // Test that a declaration in an inner scope shadows a constant
+        // of an outer scope like the host's set does
+        let s = "
+        foo:
+          - if:
+            - '~> 1 == 1'
+            - do:
+              - '~> limit := 4'
+              - '~> limit = limit + 1'
+              - '~> limit'
+        bar:
+          - '~> limit := 4'
+        ";
+        let mut env = ASTEnvironment::new();
+        env.set_const("limit", Lit::Number(3));
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert_eq!(evaluate(&docs[0]["foo"], &mut env), Ok(Yaml::Integer(5)));
+        assert_eq!(env.get("limit"), Some(Lit::Number(3)));
+        assert_eq!(
+            evaluate(&docs[0]["bar"], &mut env),
+            Err(YamlError::EvalError(EvalError::AssignToConst(
+                "limit".to_owned()
+            )))
+        );
+    }
+
     #[test]
     fn test_while_loop() {
         let s = "
@@ -326,4 +623,75 @@ mod tests {
         assert_eq!(evaluate(&docs[0]["foo"], &mut env), Ok(Yaml::Integer(5)));
         assert_eq!(env.get("a"), Some(Lit::Number(5)));
     }
+
+    #[test]
+    fn test_changes_attributed_to_expressions() {
+        let s = "
+        beetle:
+          if:
+            - '~> another_beetle_nearby == 1'
+            - do:
+              - '~> another_beetle_nearby = 0'
+        ";
+        let mut env = ASTEnvironment::new();
+        env.set("another_beetle_nearby", Lit::Number(1));
+        env.set_track_changes(true);
+
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        evaluate(&docs[0]["beetle"], &mut env).unwrap();
+
+        let diff = env.change_log().unwrap().diff();
+        assert_eq!(
+            diff,
+            vec![Change {
+                name: "another_beetle_nearby".to_owned(),
+                old: Some(Lit::Number(1)),
+                new: Lit::Number(0),
+                expression: Some("another_beetle_nearby = 0".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_batch() {
+        let s = "
+        cricket:
+          wing_color:
+            if:
+              - '~> current_season == \"spring\"'
+              - do: red
+                else: blue
+        beetle:
+          wing_color:
+            if:
+              - '~> another_beetle_nearby == 1'
+              - do:
+                - '~> another_beetle_nearby = 0'
+                - blue
+                else: red
+        ";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+
+        let mut envs = [
+            ASTEnvironment::new(),
+            ASTEnvironment::new(),
+            ASTEnvironment::new(),
+        ];
+        envs[0].set("current_season", Lit::Str("spring".to_owned()));
+        envs[1].set("another_beetle_nearby", Lit::Number(1));
+
+        let paths = ["cricket.wing_color", "beetle.wing_color", "beetle.legs"];
+        let results = evaluate_batch(&docs[0], paths.into_iter().zip(envs.iter_mut()));
+        assert_eq!(
+            results,
+            vec![
+                Ok(Yaml::String("red".to_owned())),
+                Ok(Yaml::String("blue".to_owned())),
+                Err(YamlError::EvalError(EvalError::PathNotFound(
+                    "beetle.legs".to_owned()
+                ))),
+            ]
+        );
+        assert_eq!(envs[1].get("another_beetle_nearby"), Some(Lit::Number(0)));
+    }
 }